@@ -1,28 +1,270 @@
-/* use crate::consts::*;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use std::sync::Arc;
 
-use std::fs::File;
-use std::io::BufReader;
-use rodio::{Decoder, OutputStream, /* OutputStreamHandle, */ Sink};
+use rata_tetris::engine::{Game, GameEvent, GameState};
+use crate::config::location;
+use crate::consts::*;
+use crate::music::{self, MusicControl, Synth, Tune};
 
-pub struct AudioPlayer {
-    sink: Sink,
-    //_stream: OutputStream,
-    //stream_handle: OutputStreamHandle,
-} 
+//anything that can play the game's sounds by name - music is optional
+pub trait AudioBackend {
+    fn play(&mut self, sound: &str);
+    fn play_music(&mut self, _synth: Synth) {}
+}
+
+//used without the audio feature, or when there's no sound device to play through
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, _sound: &str) {}
+}
+
+//the best backend there is - rodio if it was built in and a device opens, otherwise silence
+fn open_backend(pack: SoundPack) -> Box<dyn AudioBackend> {
+    #[cfg(feature = "audio")]
+    if let Some(backend) = device::RodioBackend::new(pack) {
+        return Box::new(backend);
+    }
+    #[cfg(not(feature = "audio"))]
+    let _ = pack;
+    Box::new(NullBackend)
+}
+
+//someone's own sounds standing in for some or all of the built in ones, read in whole from their pack's directory
+#[derive(Default)]
+pub struct SoundPack {
+    sounds: Vec<(&'static str, Arc<[u8]>)>,
+}
+
+impl SoundPack {
+    pub fn new(sounds: Vec<(&'static str, Vec<u8>)>) -> Self {
+        Self { sounds: sounds.into_iter().map(|(name, bytes)| (name, bytes.into())).collect() }
+    }
+
+    //the manifest maps sound names to files next to it, e.g. slam = "thud.ogg" - whether they decode is only found out when they play
+    pub fn files_from_toml(text: &str) -> Result<Vec<(&'static str, String)>, String> {
+        let files: BTreeMap<String, String> = toml::from_str(text).map_err(|e| e.message().to_string() + &location(text, e.span()))?;
+        files.into_iter().map(|(name, file)| {
+            let sound = SOUND_NAMES.into_iter().find(|sound| *sound == name)
+                .ok_or_else(|| format!("\"{name}\" isn't one of {}", SOUND_NAMES.join(", ")))?;
+            let extension = Path::new(&file).extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase();
+            if !SOUND_PACK_EXTENSIONS.contains(&extension.as_str()) {
+                return Err(format!("{name}: \"{file}\" isn't a {} file", SOUND_PACK_EXTENSIONS.join(" or ")));
+            }
+            //files sit right next to the manifest, so a pack can't reach anywhere else on disk
+            if !is_plain_name(&file) {
+                return Err(format!("{name}: \"{file}\" has to be a file in the pack's own directory"));
+            }
+            Ok((sound, file))
+        }).collect()
+    }
+
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    fn sound(&self, name: &str) -> Option<Arc<[u8]>> {
+        self.sounds.iter().find(|(sound, _)| *sound == name).map(|(_, bytes)| bytes.clone())
+    }
+}
+
+//a single file or directory name, with nothing leading up or out of where it's joined on
+pub fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+//sound effects and music together, for the game loop to feed events and game state into
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    music: MusicControl,
+}
+
+impl Audio {
+    pub fn open(tune: Option<&Tune>, pack: SoundPack) -> Self {
+        let mut backend = open_backend(pack);
+        let music = MusicControl::new();
+        if let Some(tune) = tune {
+            backend.play_music(Synth::new(tune, music.clone()));
+        }
+        Self { backend, music }
+    }
+
+    pub fn play(&mut self, sound: &str) {
+        self.backend.play(sound);
+    }
+
+    //plays whatever a frame's events call for, each sound once however many times it came up
+    pub fn play_events(&mut self, events: &[GameEvent]) {
+        let mut sounds: Vec<&str> = vec![];
+        for event in events {
+            let sound = match event {
+                GameEvent::GameStarted => {
+                    self.music.restart();
+                    SOUND_PLAY
+                },
+                GameEvent::Paused => SOUND_PAUSE,
+                GameEvent::Resumed => SOUND_RESUME,
+                GameEvent::Moved { offset } if *offset == LEFT_OFFSET => SOUND_MOVE_LEFT,
+                GameEvent::Moved { offset } if *offset == RIGHT_OFFSET => SOUND_MOVE_RIGHT,
+                GameEvent::Rotated { direction: ROT_LEFT } => SOUND_ROTATE_LEFT,
+                GameEvent::Rotated { .. } => SOUND_ROTATE_RIGHT,
+                GameEvent::HardDropped { .. } => SOUND_SLAM,
+                GameEvent::Locked { .. } => SOUND_PLACE,
+                GameEvent::LinesCleared { count: 4 } => SOUND_TETRIS,
+                GameEvent::LinesCleared { .. } => SOUND_LINE_CLEAR,
+                GameEvent::TopOut => SOUND_GAME_OVER,
+                //a new top score gets its fanfare instead of the game over sound
+                GameEvent::NewTopScore { .. } => {
+                    sounds.retain(|sound| *sound != SOUND_GAME_OVER);
+                    SOUND_NEW_TOP_SCORE
+                },
+                _ => continue,
+            };
+            if !sounds.contains(&sound) {
+                sounds.push(sound);
+            }
+        }
+        sounds.into_iter().for_each(|sound| self.backend.play(sound));
+    }
+
+    //the music only plays while a game is being played, and keeps pace with the level and the stack
+    pub fn update(&self, game: &Game) {
+        self.music.set_playing(game.game_state == GameState::Playing);
+        self.music.set_tempo(music::tempo(game.current_level, music::stack_height(&game.board_state)));
+    }
+}
 
-impl AudioPlayer {
-    pub fn new() -> Self {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).unwrap();
-        Self {
-            sink,
+#[cfg(feature = "audio")]
+mod device {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+
+    use super::{AudioBackend, SoundPack};
+    use crate::consts::*;
+    use crate::music::Synth;
+
+    //the wavs are built into the binary, so the game sounds the same wherever it's run from
+    const SOUNDS: [(&str, &[u8]); 14] = [
+        (SOUND_STARTUP, include_bytes!("../sounds/startup.wav")),
+        (SOUND_PAUSE, include_bytes!("../sounds/pause.wav")),
+        (SOUND_RESUME, include_bytes!("../sounds/resume.wav")),
+        (SOUND_PLAY, include_bytes!("../sounds/play.wav")),
+        (SOUND_GAME_OVER, include_bytes!("../sounds/game_over.wav")),
+        (SOUND_MOVE_LEFT, include_bytes!("../sounds/left_movement.wav")),
+        (SOUND_MOVE_RIGHT, include_bytes!("../sounds/right_movement.wav")),
+        (SOUND_ROTATE_LEFT, include_bytes!("../sounds/left_rotation.wav")),
+        (SOUND_ROTATE_RIGHT, include_bytes!("../sounds/right_rotation.wav")),
+        (SOUND_SLAM, include_bytes!("../sounds/slam.wav")),
+        (SOUND_PLACE, include_bytes!("../sounds/place.wav")),
+        (SOUND_LINE_CLEAR, include_bytes!("../sounds/clear_line.wav")),
+        (SOUND_TETRIS, include_bytes!("../sounds/tetris_sound.wav")),
+        (SOUND_NEW_TOP_SCORE, include_bytes!("../sounds/top_score.wav")),
+    ];
+
+    //the stream has to be kept alive for as long as anything plays through its handle
+    pub struct RodioBackend {
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+        pack: SoundPack,
+    }
+
+    impl RodioBackend {
+        pub fn new(pack: SoundPack) -> Option<Self> {
+            let (stream, handle) = OutputStream::try_default().ok()?;
+            Some(Self { _stream: stream, handle, pack })
+        }
+
+        fn play_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(&self, bytes: B) -> bool {
+            match Decoder::new(Cursor::new(bytes)) {
+                Ok(source) => self.handle.play_raw(source.convert_samples()).is_ok(),
+                Err(_) => false,
+            }
         }
     }
 
-    pub fn play_sound(&self, sound_name: String) {
-        let file = BufReader::new(File::open(format!("./sounds/{}.wav", sound_name)).unwrap());
-        let source = Decoder::new(file).unwrap();
-        self.sink.append(source);
+    impl AudioBackend for RodioBackend {
+        //sounds overlap rather than queue - a pack's sound goes first, and the built in one stands in if it won't decode
+        fn play(&mut self, sound: &str) {
+            if self.pack.sound(sound).is_some_and(|bytes| self.play_bytes(bytes)) { return; }
+            if let Some((_, bytes)) = SOUNDS.iter().find(|(name, _)| *name == sound) {
+                self.play_bytes(*bytes);
+            }
+        }
+
+        fn play_music(&mut self, synth: Synth) {
+            let _ = self.handle.play_raw(synth);
+        }
+    }
+
+    //the synth never runs out, it plays silence while the music is stopped
+    impl Source for Synth {
+        fn current_frame_len(&self) -> Option<usize> { None }
+        fn channels(&self) -> u16 { 1 }
+        fn sample_rate(&self) -> u32 { MUSIC_SAMPLE_RATE }
+        fn total_duration(&self) -> Option<Duration> { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    //a backend that writes down what it was asked to play
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl AudioBackend for Recorder {
+        fn play(&mut self, sound: &str) {
+            self.0.borrow_mut().push(sound.to_string());
+        }
+    }
+
+    fn played(events: &[GameEvent]) -> Vec<String> {
+        let sounds = Rc::new(RefCell::new(vec![]));
+        let mut audio = Audio { backend: Box::new(Recorder(sounds.clone())), music: MusicControl::new() };
+        audio.play_events(events);
+        sounds.take()
+    }
+
+    #[test]
+    fn each_sound_plays_once_a_frame() {
+        let events = [
+            GameEvent::Moved { offset: LEFT_OFFSET },
+            GameEvent::Moved { offset: (0, 5) },
+            GameEvent::HardDropped { rows: 5 },
+            GameEvent::Locked { mino: 1 },
+            GameEvent::LinesCleared { count: 4 },
+            GameEvent::Moved { offset: LEFT_OFFSET },
+        ];
+        assert_eq!(played(&events), [SOUND_MOVE_LEFT, SOUND_SLAM, SOUND_PLACE, SOUND_TETRIS]);
+        assert_eq!(played(&[GameEvent::Rotated { direction: ROT_LEFT }, GameEvent::LinesCleared { count: 2 }]), [SOUND_ROTATE_LEFT, SOUND_LINE_CLEAR]);
+    }
+
+    #[test]
+    fn a_new_top_score_plays_instead_of_the_game_over() {
+        assert_eq!(played(&[GameEvent::TopOut]), [SOUND_GAME_OVER]);
+        assert_eq!(played(&[GameEvent::TopOut, GameEvent::NewTopScore { score: 100 }]), [SOUND_NEW_TOP_SCORE]);
+    }
+
+    #[test]
+    fn manifest_maps_sound_names_to_files() {
+        let files = SoundPack::files_from_toml("slam = \"thud.ogg\"\nplace = \"click.WAV\"\n").unwrap();
+        assert_eq!(files, [(SOUND_PLACE, "click.WAV".to_string()), (SOUND_SLAM, "thud.ogg".to_string())]);
+        assert!(SoundPack::files_from_toml("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn manifest_mistakes_are_refused() {
+        let error = |text: &str| SoundPack::files_from_toml(text).err().unwrap_or_default();
+        assert!(error("boom = \"boom.wav\"").starts_with("\"boom\" isn't one of startup, "));
+        assert_eq!(error("slam = \"thud.mp3\""), "slam: \"thud.mp3\" isn't a wav or ogg file");
+        assert!(error("\nslam = thud.ogg").ends_with("(line 2)"));
+        //nothing outside the pack's own directory
+        for file in ["../thud.ogg", "/tmp/thud.ogg", "sounds/thud.ogg", "./thud.ogg"] {
+            assert_eq!(error(&format!("slam = \"{file}\"")), format!("slam: \"{file}\" has to be a file in the pack's own directory"));
+        }
     }
 }
- */
\ No newline at end of file
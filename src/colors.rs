@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::env;
+
+use ratatui::{buffer::Buffer, style::Color};
+
+//how many colors the terminal can show - themes are drawn as they are and brought down to fit anything less than 24 bit
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    TrueColor,
+    Indexed,
+    Ansi16,
+    Mono,
+}
+
+impl ColorDepth {
+    pub const ALL: [Self; 4] = [Self::TrueColor, Self::Indexed, Self::Ansi16, Self::Mono];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TrueColor => "truecolor",
+            Self::Indexed => "256",
+            Self::Ansi16 => "16",
+            Self::Mono => "mono",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|depth| depth.name() == name)
+    }
+
+    //there's no asking a terminal what it can do, so go by what it says about itself - NO_COLOR wins over everything
+    pub fn detect() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default().to_lowercase();
+        let term = var("TERM");
+        if !var("NO_COLOR").is_empty() || term == "dumb" {
+            Self::Mono
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            Self::TrueColor
+        } else if term.contains("256color") || term.is_empty() {
+            //windows terminals don't set TERM, and have had 256 colors for years
+            Self::Indexed
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    //a color as close as this depth can get to it - reset leaves it to the terminal's own colors
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::TrueColor, _) | (_, Color::Reset) => color,
+            (Self::Mono, _) => Color::Reset,
+            //the first sixteen are left out, since terminals all have their own ideas about those
+            (Self::Indexed, Color::Rgb(r, g, b)) => Color::Indexed(nearest(16..=255, indexed_rgb, (r, g, b))),
+            (Self::Indexed, _) => color,
+            (Self::Ansi16, _) => match rgb(color) {
+                Some(color) => ANSI_COLORS[nearest(0..16, |index| ANSI_RGB[index], color)],
+                None => color,
+            },
+        }
+    }
+}
+
+//every cell of a drawn frame brought down to the depth - cells only drawn to be blank, with the same color on both sides, stay blank in mono
+pub fn adapt_buffer(buf: &mut Buffer, depth: ColorDepth) {
+    if depth == ColorDepth::TrueColor { return; }
+    //a frame only uses a handful of colors, so each is worked out once
+    let mut adapted = HashMap::new();
+    for cell in buf.content.iter_mut() {
+        if depth == ColorDepth::Mono && cell.fg == cell.bg && cell.fg != Color::Reset {
+            cell.set_symbol(" ");
+        }
+        cell.fg = *adapted.entry(cell.fg).or_insert_with(|| depth.adapt(cell.fg));
+        cell.bg = *adapted.entry(cell.bg).or_insert_with(|| depth.adapt(cell.bg));
+    }
+}
+
+//the ansi colors in the order of their indexes, and the rgb xterm gives them by default
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::Gray,
+    Color::DarkGray, Color::LightRed, Color::LightGreen, Color::LightYellow, Color::LightBlue, Color::LightMagenta, Color::LightCyan, Color::White,
+];
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0), (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0), (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+//what a color looks like in rgb, if it's one that can be worked out
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index) => Some(indexed_rgb(index)),
+        color => ANSI_COLORS.iter().position(|ansi| *ansi == color).map(|index| ANSI_RGB[index]),
+    }
+}
+
+//the 256 color palette is the ansi sixteen, a 6x6x6 cube, then 24 greys
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index as usize {
+        index @ 0..=15 => ANSI_RGB[index],
+        index @ 16..=231 => (CUBE_LEVELS[(index - 16) / 36], CUBE_LEVELS[(index - 16) / 6 % 6], CUBE_LEVELS[(index - 16) % 6]),
+        index => { let level = (8 + (index - 232) * 10) as u8; (level, level, level) },
+    }
+}
+
+//whichever of the candidates looks closest, going by straight distance in rgb
+fn nearest<T: Copy + Default>(candidates: impl Iterator<Item = T>, rgb: impl Fn(T) -> (u8, u8, u8), (r, g, b): (u8, u8, u8)) -> T {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| (r as i32 - cr as i32).pow(2) + (g as i32 - cg as i32).pow(2) + (b as i32 - cb as i32).pow(2);
+    candidates.min_by_key(|candidate| distance(rgb(*candidate))).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_goes_by_distance_in_rgb() {
+        assert_eq!(nearest(0..16, |index| ANSI_RGB[index], (250, 10, 10)), 9);
+        assert_eq!(nearest(0..16, |index| ANSI_RGB[index], (120, 120, 130)), 8);
+        assert_eq!(nearest(16..=255, indexed_rgb, (0, 0, 0)), 16);
+        assert_eq!(nearest(16..=255, indexed_rgb, (128, 128, 128)), 244);
+        assert_eq!(indexed_rgb(196), (255, 0, 0));
+        assert_eq!(indexed_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn colors_come_down_to_each_depth() {
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(ColorDepth::TrueColor.adapt(orange), orange);
+        assert_eq!(ColorDepth::Indexed.adapt(orange), Color::Indexed(208));
+        assert_eq!(ColorDepth::Indexed.adapt(Color::Indexed(3)), Color::Indexed(3));
+        assert_eq!(ColorDepth::Ansi16.adapt(orange), Color::Yellow);
+        assert_eq!(ColorDepth::Ansi16.adapt(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.adapt(Color::Blue), Color::Blue);
+        assert_eq!(ColorDepth::Mono.adapt(orange), Color::Reset);
+        assert!(ColorDepth::ALL.iter().all(|depth| depth.adapt(Color::Reset) == Color::Reset));
+    }
+
+    #[test]
+    fn mono_blanks_cells_drawn_to_be_solid() {
+        let mut buf = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+        buf.content[0].set_symbol("█").set_fg(Color::Indexed(40)).set_bg(Color::Indexed(40));
+        buf.content[1].set_symbol("x").set_fg(Color::Indexed(40)).set_bg(Color::Indexed(234));
+        adapt_buffer(&mut buf, ColorDepth::Mono);
+        assert_eq!((buf.content[0].symbol(), buf.content[1].symbol()), (" ", "x"));
+        assert!(buf.content.iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use rata_tetris::engine::{Action, GameMode, Ruleset, Scoring};
+use crate::colors::ColorDepth;
+use crate::consts::*;
+use crate::feedback::{Cue, Cues};
+use crate::keys::{is_reserved, key_name, parse_key, KeyBindings};
+use crate::music::{find_tune, Tune};
+use crate::theme::Theme;
+
+//the config file as written - names are checked once it's read so mistakes get a clear message
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    game: GameSection,
+    handling: HandlingSection,
+    display: DisplaySection,
+    sound: SoundSection,
+    feedback: FeedbackSection,
+    keys: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GameSection {
+    mode: String,
+    rules: String,
+    scoring: String,
+    level: u8,
+    next: Option<usize>,
+}
+
+impl Default for GameSection {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::Marathon.name().to_string(),
+            rules: Ruleset::Standard.name().to_string(),
+            scoring: Scoring::Classic.name().to_string(),
+            level: 0,
+            next: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct HandlingSection {
+    das: Option<u16>,
+    arr: Option<u16>,
+    sdf: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DisplaySection {
+    ghost: bool,
+    theme: String,
+    colors: String,
+}
+
+impl Default for DisplaySection {
+    fn default() -> Self {
+        Self { ghost: true, theme: THEME_LEVELS.to_string(), colors: COLORS_AUTO.to_string() }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SoundSection {
+    music: String,
+    pack: Option<String>,
+}
+
+impl Default for SoundSection {
+    fn default() -> Self {
+        Self { music: TUNES[0].name.to_string(), pack: None }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FeedbackSection {
+    tetris: String,
+    level_up: String,
+    top_out: String,
+    top_score: String,
+}
+
+impl Default for FeedbackSection {
+    fn default() -> Self {
+        Self {
+            tetris: Cue::Flash.name().to_string(),
+            level_up: Cue::Flash.name().to_string(),
+            top_out: Cue::Flash.name().to_string(),
+            top_score: Cue::Both.name().to_string(),
+        }
+    }
+}
+
+//everything the config file can set - anything left out of the file falls back to the defaults,
+//and settings left as none fall back to whatever the ruleset brings
+pub struct Config {
+    pub mode: GameMode,
+    pub ruleset: Ruleset,
+    pub scoring: Scoring,
+    pub start_level: u8,
+    pub next_count: Option<usize>,
+    pub das: Option<u16>,
+    pub arr: Option<u16>,
+    pub soft_drop_factor: Option<u16>,
+    pub ghost: bool,
+    pub theme_name: String,
+    pub colors: Option<ColorDepth>, //none goes by what the terminal says it can do
+    pub theme: Theme, //themes can be files of their own, so this is only looked up by name once the config is loaded
+    pub music: Option<&'static Tune>,
+    pub sound_pack: Option<String>,
+    pub cues: Cues,
+    pub keys: KeyBindings,
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.message().to_string() + &location(text, e.span()))?;
+
+        let mode = GameMode::from_name(&file.game.mode)
+            .ok_or_else(|| unknown("game.mode", &file.game.mode, &GameMode::ALL.map(GameMode::name)))?;
+        let ruleset = Ruleset::from_name(&file.game.rules)
+            .ok_or_else(|| unknown("game.rules", &file.game.rules, &Ruleset::ALL.map(Ruleset::name)))?;
+        let scoring = Scoring::from_name(&file.game.scoring)
+            .ok_or_else(|| unknown("game.scoring", &file.game.scoring, &Scoring::ALL.map(Scoring::name)))?;
+        if file.game.level > MAX_START_LEVEL {
+            return Err(format!("game.level is {}, it can be 0 to {MAX_START_LEVEL}", file.game.level));
+        }
+        if let Some(next) = file.game.next.filter(|next| !(1..=MAX_NEXT_COUNT).contains(next)) {
+            return Err(format!("game.next is {next}, it can be 1 to {MAX_NEXT_COUNT}"));
+        }
+        if file.handling.sdf == Some(0) {
+            return Err("handling.sdf is 0, it needs to be at least 1".to_string());
+        }
+
+        let colors = match file.display.colors.as_str() {
+            COLORS_AUTO => None,
+            name => Some(ColorDepth::from_name(name).ok_or_else(|| {
+                let mut depths = vec![COLORS_AUTO];
+                depths.extend(ColorDepth::ALL.map(ColorDepth::name));
+                unknown("display.colors", name, &depths)
+            })?),
+        };
+
+        let music = match file.sound.music.as_str() {
+            MUSIC_OFF => None,
+            name => Some(find_tune(name).ok_or_else(|| {
+                let mut tunes = TUNES.iter().map(|tune| tune.name).collect::<Vec<&str>>();
+                tunes.push(MUSIC_OFF);
+                unknown("sound.music", name, &tunes)
+            })?),
+        };
+
+        let cue = |key: &str, name: &str| Cue::from_name(name).ok_or_else(|| unknown(key, name, &Cue::ALL.map(Cue::name)));
+        let cues = Cues {
+            tetris: cue("feedback.tetris", &file.feedback.tetris)?,
+            level_up: cue("feedback.level_up", &file.feedback.level_up)?,
+            top_out: cue("feedback.top_out", &file.feedback.top_out)?,
+            top_score: cue("feedback.top_score", &file.feedback.top_score)?,
+        };
+
+        let mut keys = KeyBindings::default();
+        for (name, key_names) in &file.keys {
+            let action = Action::from_name(name)
+                .ok_or_else(|| unknown("keys", name, &Action::ALL.map(Action::name)))?;
+            let codes = key_names.iter().map(|key| {
+                let code = parse_key(key).ok_or_else(|| format!("keys.{name}: \"{key}\" isn't a key this game knows"))?;
+                if is_reserved(code) {
+                    return Err(format!("keys.{name}: \"{key}\" is taken by pause and quit"));
+                }
+                Ok(code)
+            }).collect::<Result<Vec<_>, String>>()?;
+            keys.bind(action, codes);
+        }
+        if let Some((code, action, other)) = keys.conflict() {
+            return Err(format!("keys: \"{}\" is bound to both {} and {}", key_name(code), action.name(), other.name()));
+        }
+
+        Ok(Self {
+            mode,
+            ruleset,
+            scoring,
+            start_level: file.game.level,
+            next_count: file.game.next,
+            das: file.handling.das,
+            arr: file.handling.arr,
+            soft_drop_factor: file.handling.sdf,
+            ghost: file.display.ghost,
+            theme_name: file.display.theme,
+            colors,
+            theme: Theme::default(),
+            music,
+            sound_pack: file.sound.pack,
+            cues,
+            keys,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        //the default file always parses
+        Self::from_toml(DEFAULT_CONFIG).unwrap_or_else(|e| panic!("default config is broken: {e}"))
+    }
+}
+
+//rewrite the [keys] section of a config file with new bindings, keeping its comments and everything else as it was
+pub fn replace_keys(text: &str, keys: &KeyBindings) -> String {
+    let bindings = Action::ALL.iter().map(|action| {
+        //written as toml strings so keys like " and \ come out quoted properly
+        let names = keys.keys(*action).iter().map(|code| toml::Value::from(key_name(*code)).to_string()).collect::<Vec<String>>();
+        format!("{} = [{}]", action.name(), names.join(", "))
+    }).collect::<Vec<String>>();
+
+    let mut lines = vec![];
+    let mut in_keys = false;
+    let mut found = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[[") && trimmed.ends_with(']') {
+            if in_keys { lines.extend(bindings.iter().cloned()); }
+            in_keys = trimmed == "[keys]";
+            found |= in_keys;
+        }
+        //old bindings are dropped, even ones spread over several lines, and comments stay
+        if in_keys && trimmed != "[keys]" && !trimmed.is_empty() && !trimmed.starts_with('#') { continue; }
+        lines.push(line.to_string());
+    }
+    if in_keys {
+        lines.extend(bindings.iter().cloned());
+    } else if !found {
+        lines.push(String::new());
+        lines.push("[keys]".to_string());
+        lines.extend(bindings.iter().cloned());
+    }
+    lines.join("\n") + "\n"
+}
+
+fn unknown(key: &str, value: &str, expected: &[&str]) -> String {
+    format!("{key}: \"{value}\" isn't one of {}", expected.join(", "))
+}
+
+//toml points at a byte range, turn it into a line number for people
+pub fn location(text: &str, span: Option<std::ops::Range<usize>>) -> String {
+    span.map_or(String::new(), |span| format!(" (line {})", text[..span.start.min(text.len())].lines().count().max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::KeyCode;
+
+    use super::*;
+
+    //the message a broken config file is turned away with
+    fn error(text: &str) -> String {
+        Config::from_toml(text).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn settings_left_out_fall_back_to_the_defaults() {
+        let config = Config::from_toml("[game]\nmode = \"sprint\"\n").unwrap();
+        assert!(config.mode == GameMode::Sprint && config.ruleset == Ruleset::Standard);
+        assert_eq!((config.next_count, config.das), (None, None));
+        assert_eq!(config.keys.keys(Action::Hold), [KeyCode::Char('c')]);
+        assert!(Config::from_toml(DEFAULT_CONFIG).is_ok());
+    }
+
+    #[test]
+    fn mistakes_say_what_and_where() {
+        assert_eq!(error("[game]\nmode = \"zen\""), "game.mode: \"zen\" isn't one of marathon, sprint, ultra");
+        assert_eq!(error("[game]\nlevel = 25"), format!("game.level is 25, it can be 0 to {MAX_START_LEVEL}"));
+        assert_eq!(error("[handling]\nsdf = 0"), "handling.sdf is 0, it needs to be at least 1");
+        assert_eq!(error("[keys]\nhold = [\"banana\"]"), "keys.hold: \"banana\" isn't a key this game knows");
+        assert_eq!(error("[keys]\nhold = [\"left\"]"), "keys: \"left\" is bound to both move_left and hold");
+        assert!(error("\n\n[game]\nlevel = \"one\"").ends_with("(line 4)"));
+        assert!(error("[gaem]").ends_with("(line 1)"));
+    }
+
+    #[test]
+    fn replacing_keys_keeps_everything_else() {
+        let mut keys = KeyBindings::default();
+        keys.bind(Action::Hold, vec![KeyCode::Char('v'), KeyCode::Tab]);
+        let text = "# my settings\n[keys]\n# hold is on c\nhold = [\n  \"c\",\n]\n\n[display]\nghost = false\n";
+        let replaced = replace_keys(text, &keys);
+        assert!(replaced.starts_with("# my settings\n[keys]\n# hold is on c\n"));
+        assert!(replaced.contains("hold = [\"v\", \"tab\"]\n"));
+        assert!(!replaced.contains("\"c\""));
+        assert!(replaced.ends_with("[display]\nghost = false\n"));
+        let config = Config::from_toml(&replaced).unwrap();
+        assert!(!config.ghost);
+        assert_eq!(config.keys.keys(Action::Hold), [KeyCode::Char('v'), KeyCode::Tab]);
+
+        //keys that mean something inside a toml string still come back as themselves
+        keys.bind(Action::Hold, vec![KeyCode::Char('"'), KeyCode::Char('\\')]);
+        let config = Config::from_toml(&replace_keys(text, &keys)).unwrap();
+        assert_eq!(config.keys.keys(Action::Hold), [KeyCode::Char('"'), KeyCode::Char('\\')]);
+
+        //a file with no keys section gets one on the end
+        assert!(replace_keys("[game]\nlevel = 3\n", &keys).contains("\n[keys]\n"));
+    }
+}
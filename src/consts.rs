@@ -1,10 +1,10 @@
-//common type aliases
-pub type Rotation = Vec<Vec<u8>>;
-pub type Score = u32;
-pub type BoardXY = (i16, i16);
-pub type BlockIndex = usize;
+pub use rata_tetris::engine::consts::*;
+use rata_tetris::engine::Action;
 
-/* pub const SOUND_STARTUP: &str = "startup";
+use crate::music::Tune;
+
+//sound effects by name, which is also the name of their wav in sounds/
+pub const SOUND_STARTUP: &str = "startup";
 pub const SOUND_PAUSE: &str = "pause";
 pub const SOUND_RESUME: &str = "resume";
 pub const SOUND_PLAY: &str = "play";
@@ -17,52 +17,153 @@ pub const SOUND_SLAM: &str = "slam";
 pub const SOUND_PLACE: &str = "place";
 pub const SOUND_LINE_CLEAR: &str = "clear_line";
 pub const SOUND_TETRIS: &str = "tetris_sound";
-pub const SOUND_NEW_TOP_SCORE: &str = "top_score"; */
-
-pub const BASE_SCORES: [u32; 5] = [0, 40, 100, 300, 1200];
-
-pub const J_BLOCK: u8 = 1;
-pub const Z_BLOCK: u8 = 2;
-pub const O_BLOCK: u8 = 3;
-pub const S_BLOCK: u8 = 4;
-pub const T_BLOCK: u8 = 5;
-pub const L_BLOCK: u8 = 6;
-pub const I_BLOCK: u8 = 7;
-
-pub const STATE_PLAYING: u8 = 0;
-pub const STATE_PAUSED: u8 = 1;
-pub const STATE_GAME_OVER: u8 = 2;
-pub const STATE_START_SCREEN: u8 = 3;
-pub type GameState = u8;
-
-pub const MINO_TYPES: u8 = 7;
-
-pub const TOP_SCORE_FILENAME: &str = "top_score";
-
-//x and y for the actual play area
-pub const GAME_BOARD_WIDTH: usize = 10;
-pub const GAME_BOARD_HEIGHT: usize = 20;
-
-//directional offsets for the games movement methods
-pub const LEFT_OFFSET:  BoardXY = (-2, 0);
-pub const RIGHT_OFFSET: BoardXY = (2, 0);
-pub const DOWN_OFFSET:  BoardXY = (0, 1);
-pub const NO_OFFSET:    BoardXY = (0, 0);
-
-//match clarity
-pub const ROT_LEFT: u8 = 0;
-pub const ROT_RIGHT: u8 = 1;
-
-//signal identifiers for the game timer thread
-pub const SIGNAL_INCREASE: u8 = 1;
-pub const SIGNAL_PAUSE: u8 = 2;
-pub const SIGNAL_UNPAUSE: u8 = 3;
-pub const SIGNAL_KILL: u8 = 4;
-pub const SIGNAL_RESET: u8 = 5;
-pub const SIGNAL_DROP: () = ();
+pub const SOUND_NEW_TOP_SCORE: &str = "top_score";
+pub const SOUND_NAMES: [&str; 14] = [
+    SOUND_STARTUP, SOUND_PAUSE, SOUND_RESUME, SOUND_PLAY, SOUND_GAME_OVER, SOUND_MOVE_LEFT, SOUND_MOVE_RIGHT,
+    SOUND_ROTATE_LEFT, SOUND_ROTATE_RIGHT, SOUND_SLAM, SOUND_PLACE, SOUND_LINE_CLEAR, SOUND_TETRIS, SOUND_NEW_TOP_SCORE,
+];
+
+//sound packs are directories of their own under the data directory, with a manifest mapping sound names to files
+pub const SOUND_PACK_DIRECTORY_NAME: &str = "sound_packs";
+pub const SOUND_PACK_MANIFEST: &str = "pack.toml";
+pub const SOUND_PACK_EXTENSIONS: [&str; 2] = ["wav", "ogg"];
+
+//the music synth, mixed down to one channel
+pub const MUSIC_SAMPLE_RATE: u32 = 44100;
+pub const MUSIC_MELODY_VOLUME: f32 = 0.12;
+pub const MUSIC_BASS_VOLUME: f32 = 0.18;
+pub const MUSIC_DRUM_VOLUME: f32 = 0.05;
+pub const MUSIC_PULSE_DUTY: f32 = 0.25;
+pub const MUSIC_GATE: f32 = 0.85; //how much of each note sounds before it lets go
+pub const MUSIC_KICK_CLOCK: f32 = 1800.0;
+pub const MUSIC_SNARE_CLOCK: f32 = 7000.0;
+pub const MUSIC_HAT_CLOCK: f32 = 22050.0;
+pub const MUSIC_OFF: &str = "off";
+
+//every level adds a little tempo up to a point, and a stack this high picks the pace right up
+pub const MUSIC_LEVEL_SPEEDUP: f32 = 0.02;
+pub const MUSIC_MAX_SPEEDUP_LEVEL: u8 = 20;
+pub const MUSIC_DANGER_HEIGHT: usize = 14;
+pub const MUSIC_DANGER_SPEEDUP: f32 = 1.25;
+
+//how many frames a border flash stays lit, in whichever of the theme's flash colors the event gets
+pub const FLASH_FRAMES: u16 = 12;
+pub const BELL: &[u8] = b"\x07";
+
+//public domain tunes - korobeiniki is the russian folk song everyone knows from the game boy, then petzold's minuet in g
+pub const TUNES: [Tune; 2] = [
+    Tune {
+        name: "korobeiniki",
+        bpm: 150.0,
+        melody: "[ e5:4 b4:2 c5:2 d5:4 c5:2 b4:2 a4:4 a4:2 c5:2 e5:4 d5:2 c5:2 b4:6 c5:2 d5:4 e5:4 c5:4 a4:4 a4:4 r:4 \
+                 r:2 d5:4 f5:2 a5:4 g5:2 f5:2 e5:6 c5:2 e5:4 d5:2 c5:2 b4:4 b4:2 c5:2 d5:4 e5:4 c5:4 a4:4 a4:4 r:4 ]2 \
+                 e5:8 c5:8 d5:8 b4:8 c5:8 a4:8 g#4:8 b4:8 e5:8 c5:8 d5:8 b4:8 c5:4 e5:4 a5:8 g#5:16",
+        bass: "[ [ e2:2 e3:2 ]4 [ a2:2 a3:2 ]4 [ g#2:2 g#3:2 ]4 [ a2:2 a3:2 ]4 \
+               [ d2:2 d3:2 ]4 [ c2:2 c3:2 ]4 [ b1:2 b2:2 ]2 [ e2:2 e3:2 ]2 [ a2:2 a3:2 ]4 ]2 \
+               [ [ a2:2 a3:2 ]4 [ g#2:2 g#3:2 ]4 ]4",
+        drums: "[ k:4 h:2 h:2 s:4 h:2 h:2 ]24",
+    },
+    Tune {
+        name: "minuet",
+        bpm: 120.0,
+        melody: "d5:4 g4:2 a4:2 b4:2 c5:2 d5:4 g4:4 g4:4 e5:4 c5:2 d5:2 e5:2 f#5:2 g5:4 g4:4 g4:4 \
+                 c5:4 d5:2 c5:2 b4:2 a4:2 b4:4 c5:2 b4:2 a4:2 g4:2 f#4:4 g4:2 a4:2 b4:2 g4:2 a4:12 \
+                 d5:4 g4:2 a4:2 b4:2 c5:2 d5:4 g4:4 g4:4 e5:4 c5:2 d5:2 e5:2 f#5:2 g5:4 g4:4 g4:4 \
+                 c5:4 d5:2 c5:2 b4:2 a4:2 b4:4 c5:2 b4:2 a4:2 g4:2 a4:4 b4:2 a4:2 g4:2 f#4:2 g4:12",
+        bass: "g2:12 b2:12 c3:12 b2:12 a2:12 g2:12 d3:12 d2:12 g2:12 b2:12 c3:12 b2:12 a2:12 g2:12 d3:12 g2:12",
+        drums: "[ k:4 h:4 h:4 ]16",
+    },
+];
+
+pub const LEGACY_TOP_SCORE_FILENAME: &str = "top_score";
+pub const SPRINT_BEST_FILENAME: &str = "sprint_best";
+pub const HIGH_SCORES_FILENAME: &str = "high_scores";
+pub const SUSPENDED_GAME_FILENAME: &str = "suspended_game";
+
+//each mode keeps a table of its ten best, names are short like an arcade's initials
+pub const HIGH_SCORE_COUNT: usize = 10;
+pub const HIGH_SCORE_NAME_LENGTH: usize = 8;
+pub const DEFAULT_HIGH_SCORE_NAME: &str = "???";
+pub const LEGACY_HIGH_SCORE_NAME: &str = "---";
+
+//saved games live under the user's data directory, settings under their config directory
+pub const DATA_DIRECTORY_NAME: &str = "rata-tetris";
+pub const CONFIG_DIRECTORY_NAME: &str = "rata-tetris";
+pub const CONFIG_FILENAME: &str = "config.toml";
+pub const REPLAY_DIRECTORY_NAME: &str = "replays";
+pub const REPLAY_EXTENSION: &str = "replay";
+
+//keys each action starts out on, and the keys no action may take
+pub const DEFAULT_KEYS: [(Action, &[&str]); 7] = [
+    (Action::MoveLeft, &["left"]),
+    (Action::MoveRight, &["right"]),
+    (Action::SoftDrop, &["down"]),
+    (Action::HardDrop, &["up"]),
+    (Action::RotateLeft, &["pageup", "z"]),
+    (Action::RotateRight, &["pagedown", "x"]),
+    (Action::Hold, &["c"]),
+];
+pub const RESERVED_KEYS: [&str; 2] = ["space", "q"];
+
+//written out the first time the game runs - commented out settings fall back to the ruleset's own
+pub const DEFAULT_CONFIG: &str = r#"# rata-tetris settings - delete this file to get the defaults back
+# command line flags override anything set here
+
+[game]
+# marathon, sprint or ultra
+mode = "marathon"
+# standard or nes
+rules = "standard"
+# classic or guideline
+scoring = "classic"
+# starting level, 0 to 19
+level = 0
+# how many upcoming pieces to preview, 1 to 6 - defaults to 3, or 1 for nes rules
+# next = 3
+
+[handling]
+# delayed auto shift and auto repeat rate in frames, and the soft drop factor
+# the defaults are 10, 2 and 20, or 16 and 6 for nes rules
+# das = 10
+# arr = 2
+# sdf = 20
+
+[display]
+ghost = true
+# levels changes colors every level, or pick one of its palettes to keep:
+# blurple, lime, pink, swamp, melon, lake, factory, muave, naru, cream
+# gameboy and sunset are made in 24 bit color and brought down to fit, and your own go in a themes directory next to this file as <name>.toml
+theme = "levels"
+# auto goes by the terminal and NO_COLOR, or pick truecolor, 256, 16 or mono - mono tells minos apart by texture
+colors = "auto"
+
+[sound]
+# background music while playing, with the audio feature - korobeiniki, minuet or off
+music = "korobeiniki"
+# a sound pack from the sound_packs directory in the data directory, anything it leaves out uses the built in sounds
+# pack = "my-pack"
+
+[feedback]
+# bells and border flashes for when there's no sound, like over ssh - off, bell, flash or both
+tetris = "flash"
+level_up = "flash"
+top_out = "flash"
+top_score = "both"
+
+[keys]
+# any number of keys per action - letters, digits, left, right, up, down, pageup, pagedown,
+# home, end, insert, delete, backspace, enter, tab, esc and f1 to f12 (space and q are taken)
+move_left = ["left"]
+move_right = ["right"]
+move_down = ["down"]
+slam = ["up"]
+rotate_left = ["pageup", "z"]
+rotate_right = ["pagedown", "x"]
+hold = ["c"]
+"#;
 
-//frame counts for level difficulty
-pub const GRAVITY_TABLE: [u8; 15] = [48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 4, 3, 2, 1];
+//if the loop falls further behind than this it skips ahead instead of fast forwarding
+pub const MAX_CATCH_UP_FRAMES: u32 = 10;
 
 //user interface styling, text, borders, and rect identifiers - etc...
 pub const BIG_TEXT_TETRIS: &str = r#"  ██████  ████  ██████  ████    ██    ████    ██  
@@ -78,6 +179,13 @@ pub const BIG_TEXT_PAUSED: &str = r#" ██████  ██████  
  ██      ██  ██  ██████    ████    ██████  ████   
 "#;
 
+pub const BIG_TEXT_FINISH: &str = r#" ██████  ██████  ████    ██████    ████  ██  ██   
+ ██        ██    ██  ██    ██    ██      ██  ██   
+ ████      ██    ██  ██    ██      ██    ██████   
+ ██        ██    ██  ██    ██        ██  ██  ██   
+ ██      ██████  ██  ██  ██████  ████    ██  ██   
+"#;
+
 pub const GAME_OVER_TEXT: &str = 
 r#"   ████      ██      ██  ██    ██████             
  ██        ██  ██  ██  ██  ██  ██                 
@@ -91,37 +199,52 @@ r#"   ████      ██      ██  ██    ██████
              ██  ██  ██  ██  ██      ██████       
                ██      ██    ██████  ██    ██  ██ 
 "#;
-pub const CONTROLS_TEXT: &str = " start: space quit: q rot: pgup/dn move: ←→ slam: ↑ drop: ↓";
+pub const TIME_UP_TEXT: &str = 
+r#" ██████  ██████  ██  ██  ██████                   
+   ██      ██    ██████  ██                       
+   ██      ██    ██  ██  ████                     
+   ██      ██    ██  ██  ██                       
+   ██    ██████  ██  ██  ██████                   
+                                                  
+                 ██  ██  ██████  ██               
+                 ██  ██  ██  ██  ██               
+                 ██  ██  ██████  ██               
+                 ██  ██  ██                       
+                 ██████  ██      ██               
+"#;
+pub const REPLAY_CONTROLS_TEXT: &str = " watching a replay - quit: q";
+pub const HIGH_SCORES_CONTROLS_TEXT: &str = " back: space/esc table: ←→ quit: q";
+pub const KEYS_CONTROLS_TEXT: &str = " pick: ↑↓←→ rebind: enter remove: del defaults: d back: esc";
 
 pub const ZOOM_TIP_TEXT: &str = "Tip: On many systems you can adjust the zoom. You can try\nthis by holding down the Ctrl key, and pressing +, -, or \nscrolling the mouse wheel. 💬";
 
-pub const PRECALC_SCREEN: &str = r#"████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████
-████████████████████████████████████████████████████████████████"#;
+pub const PRECALC_SCREEN: &str = r#"████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████
+████████████████████████████████████████████████████████████████████████████"#;
 
 pub const SCORE_PADDING: usize = SCREEN_WIDTH as usize - 6;
 
@@ -129,7 +252,7 @@ pub const BORDER_WIDTH_PAD: u16 = 2;
 pub const BORDER_HEIGHT_PAD: u16 = 1;
 
 //remember* when adding rects width, height, xy, and rect below - remember to push the rect into elements in ui!
-pub const CONTROLS_WIDTH: u16 = SCREEN_WIDTH - 7;
+pub const CONTROLS_WIDTH: u16 = SCREEN_WIDTH - 4;
 pub const CONTROLS_HEIGHT: u16 = 0;
 pub const CONTROL_XY: (u16, u16) = (0, SCREEN_HEIGHT - 2);
 
@@ -161,13 +284,38 @@ pub const SCORES_WIDTH: u16 = 12;
 pub const SCORES_HEIGHT: u16 = 8;
 pub const SCORES_XY: (u16, u16) = (44, 0);
 
+//the next panel is sized for the longest queue and trimmed to the configured length when drawn
+pub const NEXT_SLOT_HEIGHT: u16 = 3;
 pub const NEXT_WIDTH: u16 = 10;
-pub const NEXT_HEIGHT: u16 = 6;
-pub const NEXT_XY: (u16, u16) = (44, 11);
+pub const NEXT_HEIGHT: u16 = 3 + NEXT_SLOT_HEIGHT * MAX_NEXT_COUNT as u16;
+pub const NEXT_XY: (u16, u16) = (58, 3);
 
 pub const NEXT_INSET_WIDTH: u16 = 6;
 pub const NEXT_INSET_HEIGHT: u16 = 2;
-pub const NEXT_INSET_XY: (u16, u16) = (46, 14);
+pub const NEXT_INSET_XY: (u16, u16) = (60, 6);
+
+pub const HOLD_WIDTH: u16 = 10;
+pub const HOLD_HEIGHT: u16 = 4;
+pub const HOLD_XY: (u16, u16) = (44, 11);
+
+pub const HOLD_INSET_WIDTH: u16 = 6;
+pub const HOLD_INSET_HEIGHT: u16 = 1;
+pub const HOLD_INSET_XY: (u16, u16) = (46, 13);
+
+//sprint splits sit under the level, the finishing split is left for the results screen
+pub const SPLITS_WIDTH: u16 = 26;
+pub const SPLITS_HEIGHT: u16 = 4;
+pub const SPLITS_XY: (u16, u16) = (44, 21);
+
+//the start screen's mode, rules and starting level picker
+pub const MODE_WIDTH: u16 = SCREEN_WIDTH - 7;
+pub const MODE_HEIGHT: u16 = 3;
+pub const MODE_XY: (u16, u16) = (0, 12);
+
+//the high scores and controls screens share one panel
+pub const MENU_WIDTH: u16 = SCREEN_WIDTH - 7;
+pub const MENU_HEIGHT: u16 = 15;
+pub const MENU_XY: (u16, u16) = (0, 3);
 
 pub const BOARD_WIDTH: u16 = 20;
 pub const BOARD_HEIGHT: u16 = 21;
@@ -179,11 +327,11 @@ pub const LEVEL_XY: (u16, u16) = (44, 18);
 
 pub const BIG_TEXT_WIDTH: u16 = 50;
 pub const BIG_TEXT_HEIGHT: u16 = 6;
-pub const BIG_TEXT_XY: (u16, u16) = (5, 3);
+pub const BIG_TEXT_XY: (u16, u16) = (10, 3);
 
 pub const GAME_OVER_TEXT_WIDTH: u16 = 50;
 pub const GAME_OVER_TEXT_HEIGHT: u16 = 12;
-pub const GAME_OVER_TEXT_XY: (u16, u16) = (5, 3);
+pub const GAME_OVER_TEXT_XY: (u16, u16) = (10, 3);
 
 //rects for the playing screen
 pub const RECT_STATS: usize = 0;
@@ -201,33 +349,40 @@ pub const RECT_NEXT_INSET: usize = 11;
 pub const RECT_GAME_OVER_STATS: usize = 12;
 pub const RECT_NEW_TOP_SCORE: usize = 13;
 pub const RECT_ZOOM_TIP: usize = 14;
+pub const RECT_HOLD: usize = 15;
+pub const RECT_HOLD_INSET: usize = 16;
+pub const RECT_SPLITS: usize = 17;
+pub const RECT_MODE: usize = 18;
+pub const RECT_MENU: usize = 19;
 
 pub const ELEMENTS_XY: (u16, u16) = (2, 1);
 
 pub const TEXT_STATS: &str = "    STATISTICS    \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n \n "; //doing this causes draw_render paragraphs to fill with empty space properly
 pub const TEXT_LINES: &str = "     LINES - ";
-pub const TEXT_NEXT: &str = "   NEXT\n \n \n \n ";
+pub const TEXT_NEXT: &str = "   NEXT";
+pub const TEXT_HOLD: &str = "   HOLD\n \n \n ";
 pub const TEXT_LEVEL: &str = "  LEVEL ";
+pub const TEXT_NO_TIME: &str = "-:--.--";
 
 pub const BLOCK: &str = "██";
 pub const CLEAR: &str = "░░";
+//without color each mino type gets a texture of its own instead, in mino id order - j, z, o, s, t, l, i
+pub const MONO_TEXTURES: [&str; 7] = ["[]", "##", "▓▓", "%%", "<>", "()", "██"];
 /* pub const EMPTY: &str = "  "; */
-pub const SCREEN_WIDTH: u16 = 32 * 2; // x 2 since each cell is 2 chars per block
+pub const SCREEN_WIDTH: u16 = 38 * 2; // x 2 since each cell is 2 chars per block
 pub const SCREEN_HEIGHT: u16 = 28;
-pub const BACKGROUND_COLOR: u8 = 234;
-
-
-pub const LEVEL_PALLETE_LENGH: usize = 8;
-pub const PALETTE_BLURPLE: [u8; LEVEL_PALLETE_LENGH] =[069, 063, 057, 069, 075, 033, 039, 081]; 
-pub const PALETTE_LIME: [u8; LEVEL_PALLETE_LENGH] =   [040, 046, 047, 034, 028, 022, 082, 082 + 2]; //most + 2 are fine - some adjusted for custom
-pub const PALETTE_PINK: [u8; LEVEL_PALLETE_LENGH] =   [219, 213, 207, 201, 200, 199, 206, 205];
-pub const PALETTE_SWAMP: [u8; LEVEL_PALLETE_LENGH] =  [033, 063, 027, 039, 041, 047, 046, 046 + 2];
-pub const PALETTE_MELON: [u8; LEVEL_PALLETE_LENGH] =  [085, 120, 048, 199, 200, 201, 207, 207 + 2];
-pub const PALETTE_LAKE: [u8; LEVEL_PALLETE_LENGH] =   [069, 063, 057, 085, 079, 120, 115, 115 + 2];
-pub const PALETTE_FACTORY: [u8; LEVEL_PALLETE_LENGH] =[242, 244, 249, 196, 160, 124, 202, 202 + 2];
-pub const PALETTE_MUAVE: [u8; LEVEL_PALLETE_LENGH] =  [052, 088, 089, 091, 093, 141, 129, 129 + 2];
-pub const PALETTE_NARU: [u8; LEVEL_PALLETE_LENGH] =   [196, 160, 124, 020, 027, 111, 075, 214];
-pub const PALETTE_CREAM: [u8; LEVEL_PALLETE_LENGH] =  [222, 216, 221, 202, 196, 160, 228, 228 + 2];
+
+//colors come from themes - these are built in, and more can be added as files in the themes directory under the config directory
+pub const THEME_LEVELS: &str = "levels";
+pub const THEMES: [(&str, &str); 3] = [
+    (THEME_LEVELS, include_str!("../themes/levels.toml")),
+    ("gameboy", include_str!("../themes/gameboy.toml")),
+    ("sunset", include_str!("../themes/sunset.toml")),
+];
+pub const THEME_DIRECTORY_NAME: &str = "themes";
+pub const THEME_EXTENSION: &str = "toml";
+pub const THEME_PATTERN_LENGTH: usize = 7;
+pub const COLORS_AUTO: &str = "auto";
 
 
 /* pub const J_BLOCK: u8 = 1;
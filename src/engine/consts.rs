@@ -0,0 +1,121 @@
+use crate::engine::randomizer::RandomizerKind;
+
+//common type aliases
+pub type Rotation = Vec<Vec<u8>>;
+pub type Score = u32;
+pub type BoardXY = (i16, i16);
+pub type BlockIndex = usize;
+
+pub const BASE_SCORES: [u32; 5] = [0, 40, 100, 300, 1200];
+
+//guideline scoring by lines cleared - everything but drop points is multiplied by (level + 1)
+pub const GUIDELINE_SCORES: [u32; 5] = [0, 100, 300, 500, 800];
+pub const TSPIN_MINI_SCORES: [u32; 3] = [100, 200, 400];
+pub const TSPIN_SCORES: [u32; 4] = [400, 800, 1200, 1600];
+pub const PERFECT_CLEAR_SCORES: [u32; 5] = [0, 800, 1200, 1800, 2000];
+pub const COMBO_SCORE: u32 = 50;
+pub const SOFT_DROP_SCORE: u32 = 1;
+pub const HARD_DROP_SCORE: u32 = 2;
+//a t mino that needed the last srs kick test always counts as a full t-spin
+pub const TSPIN_KICK_TEST: usize = 4;
+
+pub const J_BLOCK: u8 = 1;
+pub const Z_BLOCK: u8 = 2;
+pub const O_BLOCK: u8 = 3;
+pub const S_BLOCK: u8 = 4;
+pub const T_BLOCK: u8 = 5;
+pub const L_BLOCK: u8 = 6;
+pub const I_BLOCK: u8 = 7;
+
+pub const MINO_TYPES: u8 = 7;
+
+//piece generation - tgm remembers the last four minos and rolls up to four times to avoid them
+pub const DEFAULT_RANDOMIZER: RandomizerKind = RandomizerKind::SevenBag;
+pub const TGM_HISTORY_LENGTH: usize = 4;
+pub const TGM_TRIES: usize = 4;
+
+//sprint races to a line goal, taking a split every so many lines along the way
+pub const SPRINT_LINES: u16 = 40;
+pub const SPRINT_SPLIT_LINES: u16 = 10;
+
+//ultra gives two minutes by default
+pub const ULTRA_SECONDS: f64 = 120.0;
+
+//how many upcoming minos the preview queue holds
+pub const DEFAULT_NEXT_COUNT: usize = 3;
+pub const MAX_NEXT_COUNT: usize = 6;
+
+//replays are plain text, the version is bumped whenever the rules change how a recording plays back
+pub const REPLAY_MAGIC: &str = "rata-tetris replay";
+pub const REPLAY_VERSION: u32 = 4;
+//the lines every replay has before its inputs
+pub const REPLAY_SETTINGS: [&str; 10] = ["mode", "time_limit", "scoring", "ruleset", "start_level", "randomizer", "seed", "das", "arr", "sdf"];
+
+//suspended games are plain text too, and only ever read back by the same version that wrote them
+pub const SNAPSHOT_MAGIC: &str = "rata-tetris snapshot";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+//x and y for the actual play area
+pub const GAME_BOARD_WIDTH: usize = 10;
+pub const GAME_BOARD_HEIGHT: usize = 20;
+
+//directional offsets for the games movement methods
+pub const LEFT_OFFSET:  BoardXY = (-2, 0);
+pub const RIGHT_OFFSET: BoardXY = (2, 0);
+pub const DOWN_OFFSET:  BoardXY = (0, 1);
+pub const NO_OFFSET:    BoardXY = (0, 0);
+
+//match clarity
+pub const ROT_LEFT: u8 = 0;
+pub const ROT_RIGHT: u8 = 1;
+
+//super rotation system wall kicks in srs coordinates (x right, y up), tried in order until one fits
+//rows are the transitions 0->R, 0->L, R->2, R->0, 2->L, 2->R, L->0, L->2
+pub const SRS_KICK_TESTS: usize = 5;
+pub const SRS_KICKS_JLSTZ: [[BoardXY; SRS_KICK_TESTS]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+pub const SRS_KICKS_I: [[BoardXY; SRS_KICK_TESTS]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+];
+
+//the game advances one frame per tick at the nes ntsc refresh rate, everything below is counted in those frames
+pub const FRAME_RATE_NTSC: f64 = 60.0988;
+
+//lock delay in frames (roughly 500ms), and how many times moving or rotating may restart it
+pub const LOCK_DELAY_FRAMES: u16 = 30;
+pub const LOCK_RESET_LIMIT: u8 = 15;
+
+//default handling in frames - roughly 167ms before auto shift kicks in, then a move every 33ms
+pub const DEFAULT_DAS_FRAMES: u16 = 10;
+pub const DEFAULT_ARR_FRAMES: u16 = 2;
+pub const DEFAULT_SOFT_DROP_FACTOR: u16 = 20;
+
+//the nes shifts once, waits 16 frames, then moves every 6 - held soft drop falls a row every other frame
+pub const NES_DAS_FRAMES: u16 = 16;
+pub const NES_ARR_FRAMES: u16 = 6;
+pub const NES_SOFT_DROP_FRAMES: u16 = 2;
+
+//starting levels on offer at the start screen
+pub const MAX_START_LEVEL: u8 = 19;
+
+//cleared rows flash for this many frames before collapsing, a little longer for each extra row
+pub const LINE_CLEAR_FRAMES: u16 = 18;
+pub const LINE_CLEAR_FRAMES_PER_ROW: u16 = 6;
+
+//frame counts for level difficulty
+pub const GRAVITY_TABLE: [u8; 15] = [48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 4, 3, 2, 1];
@@ -0,0 +1,48 @@
+use crate::engine::consts::*;
+
+//where a game is at - the front end decides what to draw and which keys do what from this
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameState {
+    StartScreen,
+    Playing,
+    Paused,
+    GameOver,
+    Finished,
+}
+
+//everything worth reacting to, sent to subscribers the moment it happens - sound, stats and screen effects hang off these
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    GameStarted,
+    Paused,
+    Resumed,
+    PieceSpawned { mino: u8 },
+    Moved { offset: BoardXY },
+    Rotated { direction: u8 },
+    HardDropped { rows: i16 },
+    Held { mino: u8 },
+    Locked { mino: u8 },
+    LinesCleared { count: usize },
+    TSpin { lines: usize, mini: bool },
+    BackToBack,
+    Combo { count: u16 },
+    PerfectClear,
+    LevelUp { level: u8 },
+    TopOut,
+    NewTopScore { score: Score },
+    Split { lines: u16, frames: u64 },
+    Finished { frames: u64 },
+    NewPersonalBest { frames: u64 },
+    TimeUp { score: Score },
+}
+
+pub trait EventSubscriber {
+    fn on_event(&mut self, event: GameEvent);
+}
+
+//plain closures make the simplest subscribers
+impl<F: FnMut(GameEvent)> EventSubscriber for F {
+    fn on_event(&mut self, event: GameEvent) {
+        self(event);
+    }
+}
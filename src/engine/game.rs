@@ -0,0 +1,1481 @@
+use crate::engine::event::{EventSubscriber, GameEvent, GameState};
+use crate::engine::input::{Action, Handling, HeldKeys, Input};
+use crate::engine::minos::Mino;
+use crate::engine::mode::GameMode;
+use crate::engine::randomizer::{Randomizer, RandomizerKind, Rng};
+use crate::engine::replay::Replay;
+use crate::engine::ruleset::{Overrides, Ruleset};
+use crate::engine::scoring::{LineClear, Scoring, Spin};
+use crate::engine::snapshot::{Snapshot, SnapshotError};
+use crate::engine::consts::*;
+
+pub struct Game {
+    pub line_count: u16,
+    pub statistics: Vec<u16>,
+    pub top_score: u32, //the marathon score to beat, and the sprint splits below - the front end keeps the records and hands these over before each game
+    pub current_score: u32,
+    pub current_level: u8,
+    pub start_level: u8,
+    pub board_state: Vec<Vec<u8>>,
+    pub game_state: GameState,
+    pub mode: GameMode,
+    pub play_frames: u64,
+    pub splits: Vec<u64>,
+    pub best_splits: Vec<u64>,
+    pub time_limit: u64,
+    pub scoring: Scoring,
+    pub ruleset: Ruleset,
+    pub overrides: Overrides,
+    push_down: u32,
+    pub back_to_back: bool,
+    pub combo: Option<u16>,
+    pub last_clear: Option<LineClear>,
+    last_kick: Option<usize>,
+    pub current_mino: Mino,
+    pub current_mino_position: BoardXY,
+    pub next_queue: Vec<Mino>,
+    pub next_count: usize,
+    pub hold_mino: Option<Mino>,
+    pub hold_used: bool,
+    gravity_counter: u8,
+    pub slam_offset: BoardXY,
+    randomizer: Box<dyn Randomizer + Send>,
+    seeds: Rng,
+    pub fixed_seed: Option<u64>,
+    pub frame: u64,
+    recording: Option<Replay>,
+    pub playback: Option<Replay>,
+    pub rows_cleared: Vec<usize>,
+    clear_frames: u16,
+    buffered_actions: Vec<Action>,
+    lock_timer: Option<u16>,
+    lock_resets: u8,
+    lowest_row: i16,
+    pub handling: Handling,
+    held: HeldKeys,
+    subscribers: Vec<Box<dyn EventSubscriber>>,
+}
+
+impl Game {
+    //the seed is the only outside randomness the game gets - every game after the first draws its own from it
+    pub fn new(seed: u64) -> Self {
+
+        let mut seeds = Rng::new(seed);
+        let mut randomizer = DEFAULT_RANDOMIZER.create(seeds.next_u64());
+        let current_mino = Mino::new(randomizer.next_mino());
+
+        let mut game = Self {
+            line_count: 0,
+            current_level: 0,
+            start_level: 0,
+            current_score: 0,
+            top_score: 0,
+            statistics: {
+                let mut statistics: Vec<u16> = vec![0; MINO_TYPES as usize];
+                statistics[current_mino.selected_mino as usize - 1] += 1;
+                statistics
+            },
+            board_state: vec![vec![0; GAME_BOARD_WIDTH]; GAME_BOARD_HEIGHT],
+            game_state: GameState::StartScreen,
+            mode: GameMode::Marathon,
+            play_frames: 0,
+            splits: vec![],
+            best_splits: vec![],
+            time_limit: frames_from_seconds(ULTRA_SECONDS),
+            scoring: Scoring::Classic,
+            ruleset: Ruleset::Standard,
+            overrides: Overrides::default(),
+            push_down: 0,
+            back_to_back: false,
+            combo: None,
+            last_clear: None,
+            last_kick: None,
+            current_mino_position: current_mino.start_offset,
+            lowest_row: current_mino.start_offset.1,
+            current_mino,
+            next_queue: vec![],
+            next_count: DEFAULT_NEXT_COUNT,
+            hold_mino: None,
+            hold_used: false,
+            gravity_counter: 0,
+            slam_offset: NO_OFFSET,
+            randomizer,
+            seeds,
+            fixed_seed: None,
+            frame: 0,
+            recording: None,
+            playback: None,
+            rows_cleared: vec![],
+            clear_frames: 0,
+            buffered_actions: vec![],
+            lock_timer: None,
+            lock_resets: 0,
+            handling: Handling::default(),
+            held: HeldKeys::default(),
+            subscribers: vec![],
+        };
+
+        game.fill_next_queue();
+
+        game
+    }
+
+    //subscribers hear about every event as it happens, in the order they subscribed
+    pub fn subscribe(&mut self, subscriber: impl EventSubscriber + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        self.subscribers.iter_mut().for_each(|subscriber| subscriber.on_event(event));
+    }
+
+    pub fn start_game(&mut self) {
+        self.game_state = GameState::Playing;
+        self.emit(GameEvent::GameStarted);
+        //start every game from a clean slate so a replay of it begins in exactly the same place
+        self.frame = 0;
+        self.play_frames = 0;
+        self.current_level = self.start_level;
+        self.push_down = 0;
+        self.splits.clear();
+        self.back_to_back = false;
+        self.combo = None;
+        self.last_clear = None;
+        self.gravity_counter = 0;
+        self.held = HeldKeys::default();
+        self.rows_cleared.clear();
+        self.buffered_actions.clear();
+        if self.playback.is_none() {
+            let mut recording = Replay::new(self.mode, self.time_limit, self.randomizer.kind(), self.seed(), self.handling);
+            recording.scoring = self.scoring;
+            recording.ruleset = self.ruleset;
+            recording.start_level = self.start_level;
+            self.recording = Some(recording);
+        }
+    }
+
+    //feed a recorded game back through the same rules
+    pub fn start_playback(&mut self, replay: Replay) {
+        self.mode = replay.mode;
+        self.time_limit = replay.time_limit;
+        self.scoring = replay.scoring;
+        self.ruleset = replay.ruleset;
+        self.start_level = replay.start_level;
+        self.handling = replay.handling;
+        self.set_randomizer(replay.randomizer, Some(replay.seed));
+        self.playback = Some(replay);
+        //whatever was being recorded before belongs to another game
+        self.recording = None;
+        self.start_game();
+    }
+
+    fn record(&mut self, input: Input) {
+        if let Some(recording) = &mut self.recording {
+            recording.record(self.frame, input);
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.playback = None;
+        self.board_state = vec![vec![0; GAME_BOARD_WIDTH]; GAME_BOARD_HEIGHT];
+        self.line_count = 0;
+        self.statistics = vec![0; MINO_TYPES as usize];
+        self.hold_mino = None;
+        self.hold_used = false;
+        self.restart_randomizer(self.randomizer.kind());
+        self.current_score = 0;
+        
+        self.start_game();
+    }
+
+    //put a game in progress down to carry on later - held keys are let go and the game paused first, so its recording still plays back the same
+    pub fn suspend(&mut self) -> Option<Snapshot> {
+        if !matches!(self.game_state, GameState::Playing | GameState::Paused) || self.playback.is_some() { return None; }
+        if self.held.left { self.release(Action::MoveLeft); }
+        if self.held.right { self.release(Action::MoveRight); }
+        if self.held.soft_drop { self.release(Action::SoftDrop); }
+        if self.game_state == GameState::Playing { self.toggle_paused(); }
+
+        Some(Snapshot {
+            mode: self.mode,
+            time_limit: self.time_limit,
+            scoring: self.scoring,
+            ruleset: self.ruleset,
+            start_level: self.start_level,
+            handling: self.handling,
+            next_count: self.next_count,
+            randomizer: self.randomizer.kind(),
+            seed: self.randomizer.seed(),
+            randomizer_state: self.randomizer.state(),
+            seeds: self.seeds.state(),
+            fixed_seed: self.fixed_seed,
+            frame: self.frame,
+            play_frames: self.play_frames,
+            score: self.current_score,
+            level: self.current_level,
+            lines: self.line_count,
+            statistics: self.statistics.clone(),
+            splits: self.splits.clone(),
+            board: self.board_state.clone(),
+            current_mino: self.current_mino.selected_mino,
+            rotation: self.current_mino.current_rotation,
+            position: self.current_mino_position,
+            next_queue: self.next_queue.iter().map(|mino| mino.selected_mino).collect(),
+            hold_mino: self.hold_mino.as_ref().map(|mino| mino.selected_mino),
+            hold_used: self.hold_used,
+            gravity_counter: self.gravity_counter,
+            lock_timer: self.lock_timer,
+            lock_resets: self.lock_resets,
+            lowest_row: self.lowest_row,
+            last_kick: self.last_kick,
+            push_down: self.push_down,
+            back_to_back: self.back_to_back,
+            combo: self.combo,
+            rows_cleared: self.rows_cleared.clone(),
+            clear_frames: self.clear_frames,
+            buffered_actions: self.buffered_actions.clone(),
+            inputs: self.recording.as_ref().map(|recording| recording.inputs.clone()).unwrap_or_default(),
+        })
+    }
+
+    //pick a suspended game back up exactly where it was put down, still paused - one that couldn't carry on is refused and the game is left alone
+    pub fn resume(&mut self, snapshot: Snapshot) -> Result<(), SnapshotError> {
+        snapshot.validate()?;
+        self.mode = snapshot.mode;
+        self.time_limit = snapshot.time_limit;
+        self.scoring = snapshot.scoring;
+        self.ruleset = snapshot.ruleset;
+        self.start_level = snapshot.start_level;
+        self.handling = snapshot.handling;
+        self.next_count = snapshot.next_count;
+        self.randomizer = snapshot.randomizer.create(snapshot.seed);
+        self.randomizer.set_state(&snapshot.randomizer_state);
+        self.seeds = Rng::new(snapshot.seeds);
+        self.fixed_seed = snapshot.fixed_seed;
+        self.frame = snapshot.frame;
+        self.play_frames = snapshot.play_frames;
+        self.current_score = snapshot.score;
+        self.current_level = snapshot.level;
+        self.line_count = snapshot.lines;
+        self.statistics = snapshot.statistics;
+        self.splits = snapshot.splits;
+        self.board_state = snapshot.board;
+        self.current_mino = Mino::new(snapshot.current_mino);
+        self.current_mino.current_rotation = snapshot.rotation;
+        self.current_mino_position = snapshot.position;
+        self.next_queue = snapshot.next_queue.into_iter().map(Mino::new).collect();
+        self.hold_mino = snapshot.hold_mino.map(Mino::new);
+        self.hold_used = snapshot.hold_used;
+        self.gravity_counter = snapshot.gravity_counter;
+        self.lock_timer = snapshot.lock_timer;
+        self.lock_resets = snapshot.lock_resets;
+        self.lowest_row = snapshot.lowest_row;
+        self.last_kick = snapshot.last_kick;
+        self.push_down = snapshot.push_down;
+        self.back_to_back = snapshot.back_to_back;
+        self.combo = snapshot.combo;
+        self.last_clear = None;
+        self.rows_cleared = snapshot.rows_cleared;
+        self.clear_frames = snapshot.clear_frames;
+        self.buffered_actions = snapshot.buffered_actions;
+        self.held = HeldKeys::default();
+        self.slam_offset = self.find_slam_offset();
+
+        //the recording carries on from where it stopped
+        let mut recording = Replay::new(self.mode, self.time_limit, self.randomizer.kind(), self.seed(), self.handling);
+        recording.scoring = self.scoring;
+        recording.ruleset = self.ruleset;
+        recording.start_level = self.start_level;
+        recording.inputs = snapshot.inputs;
+        self.recording = Some(recording);
+        self.playback = None;
+        self.game_state = GameState::Paused;
+        Ok(())
+    }
+
+    //hands over the finished game's recording once it's over, for the front end to keep wherever it likes
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        if !self.is_over() { return None; }
+        self.recording.take()
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.game_state, GameState::GameOver | GameState::Finished)
+    }
+
+    //only marathons count towards the top score, and replays never do
+    pub fn is_new_top_score(&self) -> bool {
+        self.mode == GameMode::Marathon && self.playback.is_none() && self.current_score > self.top_score
+    }
+
+    //frames left on the ultra clock
+    pub fn time_left(&self) -> u64 {
+        self.time_limit.saturating_sub(self.play_frames)
+    }
+
+    //a finished sprint beats the best if there's no best yet or it came in under it - replays never count
+    pub fn is_new_personal_best(&self) -> bool {
+        self.mode == GameMode::Sprint
+            && self.game_state == GameState::Finished
+            && self.playback.is_none()
+            && self.best_splits.last().is_none_or(|best| self.play_frames < *best)
+    }
+
+    //advance the game by exactly one frame - the caller is responsible for calling this at the frame rate
+    //inputs happened before the frame so they go first, and while a replay plays back its inputs replace the caller's
+    pub fn step(&mut self, inputs: &[Input]) {
+
+        if self.playback.is_some() {
+            while let Some(input) = self.playback.as_mut().and_then(|playback| playback.next_input(self.frame)) {
+                self.apply(input);
+            }
+        } else {
+            inputs.iter().for_each(|input| self.apply(*input));
+        }
+
+        self.update_line_clear();
+
+        self.update_gravity();
+
+        self.auto_repeat();
+
+        self.update_lock_delay();
+
+        self.slam_offset = self.find_slam_offset();
+
+        //the clock only runs while the game is actually being played
+        if self.game_state == GameState::Playing {
+            self.play_frames += 1;
+            if self.mode == GameMode::Ultra && self.play_frames >= self.time_limit {
+                self.time_up();
+            }
+        }
+        self.frame += 1;
+    }
+
+    //find the next collision to render ghost pieces and enable piece slamming - if needed this can use an optimized collision function
+    fn find_slam_offset(&self) -> BoardXY {
+        let mut has_collided = false;
+        let mut collision_offset = NO_OFFSET;
+        while !has_collided {
+            collision_offset.1 += 1;
+            has_collided = self.collision(collision_offset, self.current_mino.get_rotation());
+        }
+        (0, collision_offset.1 - 1)
+    }
+
+    //gravity only counts frames while a mino is actually falling, so pausing and line clears hold it in place
+    fn update_gravity(&mut self) {
+        if self.game_state != GameState::Playing || !self.rows_cleared.is_empty() { return; }
+        //holding down on the nes takes over from gravity rather than adding to it
+        if self.ruleset == Ruleset::Nes && self.held.soft_drop {
+            self.gravity_counter = 0;
+            return;
+        }
+        self.gravity_counter += 1;
+        if self.gravity_counter >= gravity_frames(self.current_level) {
+            self.gravity_counter = 0;
+            //there's no lock delay on the nes, a mino locks the moment gravity can't pull it any further
+            if !self.move_mino(DOWN_OFFSET) && self.ruleset == Ruleset::Nes {
+                self.lock_mino();
+            }
+        }
+    }
+
+    //held shift keys move once on press, again after das, then every arr frames - held soft drop speeds gravity up by the soft drop factor
+    fn auto_repeat(&mut self) {
+        if self.game_state != GameState::Playing || !self.rows_cleared.is_empty() { return; }
+        if let Some(direction) = self.held.shift {
+            if self.held.das_counter < self.handling.das {
+                self.held.das_counter += 1;
+                if self.held.das_counter == self.handling.das {
+                    self.shift_repeat(direction);
+                }
+            } else {
+                self.held.arr_counter += 1;
+                if self.held.arr_counter >= self.handling.arr {
+                    self.held.arr_counter = 0;
+                    self.shift_repeat(direction);
+                }
+            }
+        }
+        if self.held.soft_drop {
+            self.held.soft_drop_counter += 1;
+            let interval = match self.ruleset {
+                Ruleset::Standard => (gravity_frames(self.current_level) as u16 / self.handling.soft_drop_factor.max(1)).max(1),
+                Ruleset::Nes => NES_SOFT_DROP_FRAMES.min(gravity_frames(self.current_level) as u16),
+            };
+            if self.held.soft_drop_counter >= interval {
+                self.held.soft_drop_counter = 0;
+                self.soft_drop();
+            }
+        }
+    }
+
+    //an arr of zero slides the mino straight to the wall
+    fn shift_repeat(&mut self, direction: BoardXY) {
+        if self.handling.arr == 0 {
+            while !self.collision(direction, self.current_mino.get_rotation()) {
+                self.move_mino(direction);
+            }
+        } else {
+            self.shift(direction);
+        }
+    }
+
+    //the nes charges das fully when a mino is pushed against something, so it slides off the moment there's room
+    fn shift(&mut self, direction: BoardXY) {
+        if self.game_state != GameState::Playing { return; }
+        if !self.move_mino(direction) && self.ruleset == Ruleset::Nes {
+            self.held.das_counter = self.handling.das;
+            self.held.arr_counter = self.handling.arr;
+        }
+    }
+
+    //a grounded mino counts up once per frame and locks when it runs out of delay, leaving the ground stops the count
+    fn update_lock_delay(&mut self) {
+        if self.game_state != GameState::Playing || !self.rows_cleared.is_empty() || self.ruleset == Ruleset::Nes { return; }
+        if self.collision(DOWN_OFFSET, self.current_mino.get_rotation()) {
+            let frames = self.lock_timer.map_or(0, |frames| frames + 1);
+            self.lock_timer = Some(frames);
+            if frames >= LOCK_DELAY_FRAMES {
+                self.lock_mino();
+            }
+        } else {
+            self.lock_timer = None;
+        }
+    }
+
+    //successful moves and rotations on the ground restart the delay, up to a limit so a mino can't be stalled forever
+    fn reset_lock_delay(&mut self) {
+        if self.lock_timer.is_some() && self.lock_resets < LOCK_RESET_LIMIT {
+            self.lock_timer = Some(0);
+            self.lock_resets += 1;
+        }
+    }
+
+    pub fn collision(&self, direction: BoardXY, rotation: &Rotation) -> bool {
+
+        let new_position: BoardXY = (
+            self.current_mino_position.0 + direction.0,
+            self.current_mino_position.1 + direction.1
+        );
+        !fits(&self.board_state, rotation, new_position)
+    }
+
+    fn move_mino(&mut self, change_offset: BoardXY) -> bool {
+        if self.collision(change_offset, self.current_mino.get_rotation()) {
+            //a blocked downward move no longer places the mino - the lock delay handles that
+            return false;
+        }
+        if change_offset != NO_OFFSET {
+            self.current_mino_position.0 += change_offset.0;
+            self.current_mino_position.1 += change_offset.1;
+            self.last_kick = None;
+            self.emit(GameEvent::Moved { offset: change_offset });
+            //reaching a new lowest row hands back the full set of lock resets
+            if self.current_mino_position.1 > self.lowest_row {
+                self.lowest_row = self.current_mino_position.1;
+                self.lock_resets = 0;
+            }
+            if change_offset.0 != 0 {
+                self.reset_lock_delay();
+            }
+        }
+        true
+    }
+
+    fn lock_mino(&mut self) {
+        //nes push down points are paid for the rows this mino was soft dropped, right as it locks
+        self.current_score += std::mem::take(&mut self.push_down);
+        if !self.place() {
+            self.game_over();
+            return;
+        }
+        self.hold_used = false;
+        self.check_rows();
+        //the next mino waits for the clear animation if any rows filled up, and a finished sprint doesn't get one
+        if self.rows_cleared.is_empty() && self.game_state == GameState::Playing {
+            self.spawn_next();
+        }
+    }
+
+    fn spawn_next(&mut self) {
+        self.new_mino();
+        if self.collision(DOWN_OFFSET, self.current_mino.get_rotation()) {
+            self.game_over();
+            return;
+        }
+        //anything pressed during the clear animation applies to the new mino as it enters
+        std::mem::take(&mut self.buffered_actions).into_iter().for_each(|action| self.perform(action));
+    }
+
+    //full rows are scored as soon as the mino locks, then left on the board to flash until the clear animation finishes
+    fn check_rows(&mut self) {
+        self.rows_cleared = self.board_state.iter().enumerate()
+            .filter(|(_, row)| row.iter().all(|cell| *cell != 0))
+            .map(|(index, _)| index)
+            .collect();
+        let count = self.rows_cleared.len();
+        (0..count).for_each(|_| self.increase_lines());
+
+        if count > 0 {
+            self.emit(GameEvent::LinesCleared { count });
+            self.clear_frames = LINE_CLEAR_FRAMES + LINE_CLEAR_FRAMES_PER_ROW * count as u16;
+        }
+
+        //the base points are multiplied by (level + 1) - if count was 0 no score is added
+        let base_score_earned = match self.scoring {
+            Scoring::Classic => BASE_SCORES[count],
+            Scoring::Guideline => self.guideline_score(count),
+        };
+        let score_earned = (self.current_level as u32 + 1) * base_score_earned;
+        self.current_score += score_earned;
+
+        //increase the level, once for every level passed
+        while self.current_level < self.ruleset.level_for_lines(self.start_level, self.line_count) {
+            self.increase_level();
+        }
+
+        if self.mode == GameMode::Sprint && count > 0 {
+            self.check_sprint(count as u16);
+        }
+
+    }
+
+    //guideline points before the level multiplier - also keeps the back-to-back and combo chains going
+    fn guideline_score(&mut self, count: usize) -> u32 {
+        let spin = self.t_spin();
+        let mut score = match spin {
+            Spin::None => GUIDELINE_SCORES[count],
+            Spin::Mini => TSPIN_MINI_SCORES[count.min(2)],
+            Spin::Full => TSPIN_SCORES[count.min(3)],
+        };
+
+        //tetrises and t-spins that clear lines are difficult, two in a row pays half again - a t-spin with no lines doesn't break the chain
+        let difficult = count == 4 || (spin != Spin::None && count > 0);
+        let back_to_back = difficult && self.back_to_back;
+        if back_to_back {
+            score = score * 3 / 2;
+        }
+        if count > 0 {
+            self.back_to_back = difficult;
+        }
+
+        //every lock in a row that clears lines adds to the combo
+        self.combo = if count > 0 { Some(self.combo.map_or(0, |combo| combo + 1)) } else { None };
+        let combo = self.combo.unwrap_or_default();
+        score += COMBO_SCORE * combo as u32;
+
+        //everything left on the board is in the rows being cleared
+        let perfect = count > 0 && self.board_state.iter().enumerate()
+            .all(|(index, row)| self.rows_cleared.contains(&index) || row.iter().all(|cell| *cell == 0));
+        if perfect {
+            score += PERFECT_CLEAR_SCORES[count];
+        }
+
+        if count > 0 || spin != Spin::None {
+            self.last_clear = Some(LineClear { lines: count, spin, back_to_back, combo, perfect });
+        }
+        if spin != Spin::None { self.emit(GameEvent::TSpin { lines: count, mini: spin == Spin::Mini }); }
+        if back_to_back { self.emit(GameEvent::BackToBack); }
+        if combo > 0 { self.emit(GameEvent::Combo { count: combo }); }
+        if perfect { self.emit(GameEvent::PerfectClear); }
+
+        score
+    }
+
+    //three corner rule - a t that got here by rotating with three corners filled spun, and it's a full spin if both corners it points at are filled
+    fn t_spin(&self) -> Spin {
+        let Some(kick) = self.last_kick else { return Spin::None };
+        if self.current_mino.selected_mino != T_BLOCK { return Spin::None; }
+
+        //corners of the 3x3 box top left, top right, bottom left, bottom right - walls and the floor count as filled
+        let filled = [(0, 0), (2, 0), (0, 2), (2, 2)].map(|(cell_x, cell_y): (i16, i16)| {
+            let board_x = (self.current_mino_position.0 + cell_x * 2) / 2;
+            let board_y = self.current_mino_position.1 + cell_y - 1;
+            if !(0..GAME_BOARD_WIDTH as i16).contains(&board_x) || board_y >= GAME_BOARD_HEIGHT as i16 { return true; }
+            board_y >= 0 && self.board_state[board_y as usize][board_x as usize] != 0
+        });
+        if filled.iter().filter(|corner| **corner).count() < 3 { return Spin::None; }
+
+        //the corners on the side the t points at for each rotation - down, left, up, right
+        let front = match self.current_mino.current_rotation {
+            0 => [2, 3],
+            1 => [0, 2],
+            2 => [0, 1],
+            _ => [1, 3],
+        };
+        if front.iter().all(|corner| filled[*corner]) || kick == TSPIN_KICK_TEST { Spin::Full } else { Spin::Mini }
+    }
+
+    //take a split for every goal passed on the way, and finish the race on the last line instead of topping out
+    fn check_sprint(&mut self, count: u16) {
+        let previous_lines = self.line_count - count;
+        (previous_lines + 1..=self.line_count.min(SPRINT_LINES))
+            .filter(|lines| lines % SPRINT_SPLIT_LINES == 0)
+            .for_each(|lines| {
+                self.splits.push(self.play_frames);
+                self.emit(GameEvent::Split { lines, frames: self.play_frames });
+            });
+
+        if self.line_count >= SPRINT_LINES {
+            self.game_state = GameState::Finished;
+            //the clear animation won't get to run, so the last rows go straight away rather than flashing under the results
+            self.drop_cleared_rows();
+            self.emit(GameEvent::Finished { frames: self.play_frames });
+            if self.is_new_personal_best() {
+                self.emit(GameEvent::NewPersonalBest { frames: self.play_frames });
+            }
+        }
+    }
+
+    //count the clear animation down one frame at a time, then drop the rows above into place and bring in the next mino
+    fn update_line_clear(&mut self) {
+        if self.game_state != GameState::Playing || self.rows_cleared.is_empty() { return; }
+        self.clear_frames = self.clear_frames.saturating_sub(1);
+        if self.clear_frames > 0 { return; }
+
+        self.drop_cleared_rows();
+        self.spawn_next();
+    }
+
+    //remove each cleared row in reverse order so the indexes stay valid, then refill from the top
+    fn drop_cleared_rows(&mut self) {
+        self.rows_cleared.iter().rev().for_each(|row_index| {
+            self.board_state.remove(*row_index);
+        });
+        self.rows_cleared.iter().for_each(|_| {
+            self.board_state.insert(0, vec![0; GAME_BOARD_WIDTH]);
+        });
+        self.rows_cleared.clear();
+        self.clear_frames = 0;
+    }
+
+    fn new_mino(&mut self) {
+        let mino = self.next_queue.remove(0);
+        self.increase_stat(mino.selected_mino as usize);
+        self.fill_next_queue();
+        self.spawn(mino);
+    }
+
+    fn spawn(&mut self, mino: Mino) {
+        self.current_mino = mino;
+        self.current_mino_position = self.current_mino.start_offset;
+        self.lock_timer = None;
+        self.lock_resets = 0;
+        self.lowest_row = self.current_mino_position.1;
+        self.last_kick = None;
+        self.push_down = 0;
+        self.emit(GameEvent::PieceSpawned { mino: self.current_mino.selected_mino });
+    }
+
+    //the queue always holds the longest preview so changing its length never changes the upcoming order
+    fn fill_next_queue(&mut self) {
+        while self.next_queue.len() < MAX_NEXT_COUNT {
+            self.next_queue.push(Mino::new(self.randomizer.next_mino()));
+        }
+    }
+
+    pub fn set_next_count(&mut self, count: usize) {
+        self.next_count = count.clamp(1, MAX_NEXT_COUNT);
+    }
+
+    //switching rules brings their piece generator, handling and preview along, except for whatever the overrides pick
+    pub fn set_ruleset(&mut self, ruleset: Ruleset) {
+        self.ruleset = ruleset;
+        self.handling = self.overrides.handling(ruleset);
+        self.set_next_count(self.overrides.next_count(ruleset));
+        self.set_randomizer(self.overrides.randomizer(ruleset), self.fixed_seed);
+    }
+
+    pub fn set_start_level(&mut self, level: u8) {
+        self.start_level = level.min(MAX_START_LEVEL);
+        self.current_level = self.start_level;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.randomizer.seed()
+    }
+
+    //swap the piece generator - with a fixed seed every game deals the same sequence, otherwise each game gets a fresh seed
+    pub fn set_randomizer(&mut self, kind: RandomizerKind, seed: Option<u64>) {
+        self.fixed_seed = seed;
+        self.statistics = vec![0; MINO_TYPES as usize];
+        self.restart_randomizer(kind);
+    }
+
+    fn restart_randomizer(&mut self, kind: RandomizerKind) {
+        self.randomizer = kind.create(self.fixed_seed.unwrap_or_else(|| self.seeds.next_u64()));
+        self.next_queue.clear();
+        self.fill_next_queue();
+        self.new_mino();
+    }
+
+    //false if any of the mino would lock above the board, which tops the game out instead
+    fn place(&mut self) -> bool {
+        let cells: Vec<BoardXY> = self.current_mino.get_rotation().iter().enumerate().flat_map(|(cell_y, row)| {
+            row.iter().enumerate().filter(|(_, val)| **val != 0).map(move |(cell_x, _)| (cell_x as i16, cell_y as i16))
+        }).map(|(cell_x, cell_y)| {
+            ((self.current_mino_position.0 + cell_x * 2) / 2, self.current_mino_position.1 + cell_y - 1)
+        }).collect();
+        if cells.iter().any(|(_, board_y)| *board_y < 0) {
+            return false;
+        }
+        cells.into_iter().for_each(|(board_x, board_y)| {
+            self.board_state[board_y as usize][board_x as usize] = self.current_mino.selected_mino;
+        });
+        self.emit(GameEvent::Locked { mino: self.current_mino.selected_mino });
+        true
+    }
+    
+    fn rotate_mino(&mut self, direction: u8) {
+
+        let next_rotation = self.current_mino.next_rotation(direction).clone();
+        //walk the srs kick tests in order and take the first offset the rotated mino fits at - the nes only tries rotating in place
+        let kicks = match self.ruleset {
+            Ruleset::Standard => self.current_mino.kicks(direction),
+            Ruleset::Nes => vec![NO_OFFSET],
+        };
+        let kick = kicks.into_iter().enumerate().find(|(_, kick)| !self.collision(*kick, &next_rotation));
+        if let Some((test, kick)) = kick {
+            self.current_mino_position.0 += kick.0;
+            self.current_mino_position.1 += kick.1;
+            self.current_mino.rotate(direction);
+            self.last_kick = Some(test);
+            self.emit(GameEvent::Rotated { direction });
+            self.reset_lock_delay();
+        }
+    }
+    fn apply(&mut self, input: Input) {
+        match input {
+            Input::Press(action) => self.press(action),
+            Input::Release(action) => self.release(action),
+            Input::Pause => self.toggle_paused(),
+        }
+    }
+    fn press(&mut self, action: Action) {
+        self.record(Input::Press(action));
+        match action {
+            Action::MoveLeft => {
+                self.held.left = true;
+                self.held.start_shift(LEFT_OFFSET);
+            },
+            Action::MoveRight => {
+                self.held.right = true;
+                self.held.start_shift(RIGHT_OFFSET);
+            },
+            Action::SoftDrop => {
+                self.held.soft_drop = true;
+                self.held.soft_drop_counter = 0;
+            },
+            _ => {},
+        }
+        //there's no mino to act on while rows are clearing, so hang on to the press for the next one
+        if self.game_state == GameState::Playing && !self.rows_cleared.is_empty() {
+            self.buffered_actions.push(action);
+        } else {
+            self.perform(action);
+        }
+    }
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::MoveLeft => self.move_left(),
+            Action::MoveRight => self.move_right(),
+            Action::SoftDrop => self.soft_drop(),
+            Action::RotateLeft => self.rotate_left(),
+            Action::RotateRight => self.rotate_right(),
+            //the nes had neither
+            Action::HardDrop | Action::Hold if self.ruleset == Ruleset::Nes => {},
+            Action::HardDrop => self.slam(),
+            Action::Hold => self.hold(),
+        }
+    }
+    fn release(&mut self, action: Action) {
+        self.record(Input::Release(action));
+        match action {
+            Action::MoveLeft => {
+                self.held.left = false;
+                self.held.release_shift(LEFT_OFFSET);
+            },
+            Action::MoveRight => {
+                self.held.right = false;
+                self.held.release_shift(RIGHT_OFFSET);
+            },
+            //push down rows stay banked until the mino locks - without key releases the terminal lets go in the same frame it presses
+            Action::SoftDrop => self.held.soft_drop = false,
+            _ => {},
+        }
+    }
+    pub fn move_down(&mut self) {
+        if self.game_state != GameState::Playing { return; }
+        self.move_mino(DOWN_OFFSET);
+    }
+    //a drop the player asked for, worth a point a row under guideline scoring - the nes counts rows towards push down points instead
+    fn soft_drop(&mut self) {
+        if self.game_state != GameState::Playing { return; }
+        let moved = self.move_mino(DOWN_OFFSET);
+        match self.ruleset {
+            Ruleset::Nes if moved => self.push_down += 1,
+            Ruleset::Nes => self.lock_mino(),
+            Ruleset::Standard if moved && self.scoring == Scoring::Guideline => self.current_score += SOFT_DROP_SCORE,
+            Ruleset::Standard => {},
+        }
+    }
+    pub fn move_left(&mut self) {
+        self.shift(LEFT_OFFSET);
+    }
+    pub fn move_right(&mut self) {
+        self.shift(RIGHT_OFFSET);
+    }
+    pub fn rotate_right(&mut self) {
+        if self.game_state != GameState::Playing { return; }
+        self.rotate_mino(ROT_RIGHT);
+    }
+    pub fn rotate_left(&mut self) {
+        if self.game_state != GameState::Playing { return; }
+        self.rotate_mino(ROT_LEFT);
+    }
+
+    fn increase_lines(&mut self) {
+        self.line_count += 1;
+    }
+
+    fn increase_stat(&mut self, index: BlockIndex) {
+        //this produced a crash, subtracting one since valid block indexes are 1..=7, and statistics is 0..=6; 0 represents empty cells and rerolls in the mino generator
+        self.statistics[index - 1] += 1;
+    }
+
+    fn increase_level(&mut self) -> u8 {
+        self.current_level += 1;
+        self.emit(GameEvent::LevelUp { level: self.current_level });
+        self.current_level
+    }
+
+    fn game_over(&mut self) {
+        self.game_state = GameState::GameOver;
+        self.board_state = vec![vec![0; GAME_BOARD_WIDTH]; GAME_BOARD_HEIGHT];
+        self.emit(GameEvent::TopOut);
+        //the recording waits in take_replay, and keeping the score is up to the front end
+        if self.is_new_top_score() {
+            self.emit(GameEvent::NewTopScore { score: self.current_score });
+        }
+    }
+
+    fn time_up(&mut self) {
+        self.game_state = GameState::Finished;
+        self.emit(GameEvent::TimeUp { score: self.current_score });
+    }
+    //input functions
+    pub fn slam(&mut self) {
+        if self.game_state != GameState::Playing { return; }
+        self.slam_offset = self.find_slam_offset();
+        self.move_mino(self.slam_offset);
+        if self.scoring == Scoring::Guideline {
+            self.current_score += HARD_DROP_SCORE * self.slam_offset.1 as u32;
+        }
+        self.emit(GameEvent::HardDropped { rows: self.slam_offset.1 });
+        self.lock_mino();
+    }
+    pub fn hold(&mut self) {
+        //only one swap is allowed until the current mino locks
+        if self.game_state != GameState::Playing || self.hold_used { return; }
+        let mut held = self.current_mino.clone();
+        held.current_rotation = 0;
+        match self.hold_mino.replace(held) {
+            Some(mino) => self.spawn(mino),
+            None => self.new_mino(),
+        }
+        self.hold_used = true;
+        self.emit(GameEvent::Held { mino: self.hold_mino.as_ref().map_or(0, |mino| mino.selected_mino) });
+        if self.collision(DOWN_OFFSET, self.current_mino.get_rotation()) {
+            self.game_over();
+        }
+    }
+    fn toggle_paused(&mut self) {
+        match self.game_state {
+            GameState::Paused => {
+                self.record(Input::Pause);
+                self.game_state = GameState::Playing;
+                self.emit(GameEvent::Resumed);
+            },
+            GameState::Playing => {
+                self.record(Input::Pause);
+                self.game_state = GameState::Paused;
+                self.emit(GameEvent::Paused);
+            },
+            GameState::StartScreen | GameState::GameOver | GameState::Finished => {},
+        }
+    }
+}
+
+//whether a mino turned this way fits on the board at this position - the walls, the floor and filled cells are in the way
+pub fn fits(board: &[Vec<u8>], rotation: &Rotation, position: BoardXY) -> bool {
+    !rotation.iter().enumerate().any(|(cell_y, row)| {
+        row.iter().enumerate().any(|(cell_x, value)| {
+            if *value != 0 {
+                let (board_x_pos, board_y_pos): BoardXY = ((cell_x as i16 * 2) + position.0, cell_y as i16 + position.1);
+
+                if !(0..20).contains(&board_x_pos) { //left and right walls limits
+                    return true;
+                } else if board_y_pos > 20 {//floor limit
+                    return true;
+                } else {
+
+                    let mut current_pos = position;
+                    current_pos.0 += cell_x as i16 * 2;
+                    current_pos.1 += cell_y as i16 - 1;
+                    current_pos.0 /= 2;
+
+                    //srs kicks can lift a mino past the top of the board, which is open space until it locks there
+                    return current_pos.1 >= 0 && board[current_pos.1 as usize][current_pos.0 as usize] != 0;
+                }
+            }
+            false
+        })
+    })
+}
+
+//wall clock time to whole frames at the nes rate
+pub fn frames_from_seconds(seconds: f64) -> u64 {
+    (seconds * FRAME_RATE_NTSC).round() as u64
+}
+
+fn gravity_frames(level: u8) -> u8 {
+    match level {
+        0 => GRAVITY_TABLE[0],
+        1 => GRAVITY_TABLE[1],
+        2 => GRAVITY_TABLE[2],
+        3 => GRAVITY_TABLE[3],
+        4 => GRAVITY_TABLE[4],
+        5 => GRAVITY_TABLE[5],
+        6 => GRAVITY_TABLE[6],
+        7 => GRAVITY_TABLE[7],
+        8 => GRAVITY_TABLE[8],
+        9 => GRAVITY_TABLE[9],
+        10..=12 => GRAVITY_TABLE[10],
+        13..=15 => GRAVITY_TABLE[11],
+        16..=18 => GRAVITY_TABLE[12],
+        19..=28 => GRAVITY_TABLE[13],
+        _ => GRAVITY_TABLE[14],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a game in play with the given mino turned and placed by hand
+    fn playing_with(mino: u8, rotation: usize, position: BoardXY) -> Game {
+        let mut game = Game::new(1);
+        game.start_game();
+        let mut mino = Mino::new(mino);
+        mino.current_rotation = rotation;
+        game.spawn(mino);
+        game.current_mino_position = position;
+        game
+    }
+
+    //a fixed run of presses and releases that moves, turns and drops minos all over the board
+    fn scripted_inputs(frame: u64) -> Vec<Input> {
+        const SCRIPT: [Action; 9] = [Action::MoveLeft, Action::RotateRight, Action::HardDrop, Action::MoveRight, Action::MoveRight, Action::SoftDrop, Action::RotateLeft, Action::Hold, Action::HardDrop];
+        let action = SCRIPT[(frame / 8) as usize % SCRIPT.len()];
+        match frame % 8 {
+            0 => vec![Input::Press(action)],
+            3 => vec![Input::Release(action)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same_game() {
+        let play = |seed| {
+            let mut game = Game::new(seed);
+            game.start_game();
+            (0..300).for_each(|frame| game.step(&scripted_inputs(frame)));
+            game
+        };
+        let (first, second) = (play(5), play(5));
+        assert_eq!(first.board_state, second.board_state);
+        assert_eq!(first.current_mino_position, second.current_mino_position);
+        assert_eq!(first.next_queue.iter().map(|mino| mino.selected_mino).collect::<Vec<u8>>(), second.next_queue.iter().map(|mino| mino.selected_mino).collect::<Vec<u8>>());
+        assert_eq!(first.frame, 300);
+        assert_ne!(first.board_state, play(6).board_state);
+    }
+
+    #[test]
+    fn gravity_pulls_the_mino_down_once_per_interval() {
+        let mut game = Game::new(1);
+        game.start_game();
+        let start = game.current_mino_position;
+        (0..gravity_frames(0)).for_each(|_| game.step(&[]));
+        assert_eq!(game.current_mino_position, (start.0, start.1 + 1));
+        //nothing moves while paused
+        game.step(&[Input::Pause]);
+        (0..gravity_frames(0) * 2).for_each(|_| game.step(&[]));
+        assert_eq!(game.current_mino_position, (start.0, start.1 + 1));
+    }
+
+    #[test]
+    fn playback_reproduces_the_game() {
+        let mut game = Game::new(99);
+        game.scoring = Scoring::Guideline;
+        game.start_game();
+        (0..300).for_each(|frame| game.step(&scripted_inputs(frame)));
+        assert!(game.current_score > 0 && game.board_state.iter().flatten().any(|cell| *cell != 0));
+        let replay = Replay::from_text(&game.recording.take().unwrap().to_text()).unwrap();
+
+        let mut playback = Game::new(1);
+        playback.start_playback(replay);
+        (0..300).for_each(|_| playback.step(&[]));
+        assert_eq!(playback.board_state, game.board_state);
+        assert_eq!(playback.current_score, game.current_score);
+        assert_eq!(playback.line_count, game.line_count);
+        assert_eq!(playback.statistics, game.statistics);
+        assert_eq!(playback.game_state, game.game_state);
+    }
+
+    #[test]
+    fn playback_drops_the_last_game_recording() {
+        let mut game = Game::new(99);
+        game.start_game();
+        (0..100).for_each(|frame| game.step(&scripted_inputs(frame)));
+        let replay = Replay::from_text(&game.recording.as_ref().unwrap().to_text()).unwrap();
+        game.start_playback(replay);
+        (0..100).for_each(|_| game.step(&[]));
+        assert!(game.recording.is_none());
+    }
+
+    //fill the given board cells with junk
+    fn fill(game: &mut Game, cells: &[(usize, usize)]) {
+        cells.iter().for_each(|(column, row)| game.board_state[*row][*column] = J_BLOCK);
+    }
+
+    //rows 16 to 19 full but for the right hand column, with an upright i waiting over the gap
+    fn tetris_ready(scoring: Scoring, junk: bool) -> Game {
+        let mut game = playing_with(I_BLOCK, 1, (16, 17));
+        game.scoring = scoring;
+        (16..20).for_each(|row| fill(&mut game, &(0..9).map(|column| (column, row)).collect::<Vec<(usize, usize)>>()));
+        if junk { fill(&mut game, &[(0, 15)]); }
+        game
+    }
+
+    //let the clear animation run out so the board settles and the next mino comes in
+    fn finish_clear(game: &mut Game) {
+        while !game.rows_cleared.is_empty() { game.step(&[]); }
+    }
+
+    //a t pointing down over a slot in the bottom left, with the overhang above it - the t got there by rotating
+    fn t_spin_double_ready() -> Game {
+        let mut game = playing_with(T_BLOCK, 0, (0, 18));
+        game.scoring = Scoring::Guideline;
+        fill(&mut game, &(3..10).map(|column| (column, 18)).collect::<Vec<(usize, usize)>>());
+        fill(&mut game, &[0, 2, 3, 4, 5, 6, 7, 8, 9].map(|column| (column, 19)));
+        fill(&mut game, &[(0, 17)]);
+        game.last_kick = Some(0);
+        game
+    }
+
+    #[test]
+    fn classic_tetris_pays_the_nes_base() {
+        let mut game = tetris_ready(Scoring::Classic, true);
+        game.lock_mino();
+        assert_eq!(game.rows_cleared, vec![16, 17, 18, 19]);
+        assert_eq!(game.current_score, BASE_SCORES[4]);
+    }
+
+    #[test]
+    fn back_to_back_tetrises_pay_half_again_plus_combo() {
+        let mut game = tetris_ready(Scoring::Guideline, true);
+        game.lock_mino();
+        assert_eq!(game.current_score, 800);
+        finish_clear(&mut game);
+
+        //the junk dropped down with the clear - row 15 is filled as well so this tetris leaves something behind too
+        assert_eq!(game.board_state[19][0], J_BLOCK);
+        (15..20).for_each(|row| fill(&mut game, &(0..9).map(|column| (column, row)).collect::<Vec<(usize, usize)>>()));
+        let mut i = Mino::new(I_BLOCK);
+        i.current_rotation = 1;
+        game.spawn(i);
+        game.current_mino_position = (16, 17);
+        game.lock_mino();
+        assert_eq!(game.current_score, 800 + 800 * 3 / 2 + COMBO_SCORE);
+        let clear = game.last_clear.unwrap();
+        assert!(clear.back_to_back);
+        assert_eq!(clear.combo, 1);
+    }
+
+    #[test]
+    fn clearing_everything_is_a_perfect_clear() {
+        let mut game = tetris_ready(Scoring::Guideline, false);
+        game.lock_mino();
+        assert!(game.last_clear.unwrap().perfect);
+        assert_eq!(game.current_score, 800 + PERFECT_CLEAR_SCORES[4]);
+    }
+
+    #[test]
+    fn t_spin_double_is_detected_and_scored() {
+        let mut game = t_spin_double_ready();
+        assert_eq!(game.t_spin(), Spin::Full);
+        game.lock_mino();
+        assert_eq!(game.rows_cleared, vec![18, 19]);
+        assert_eq!(game.current_score, TSPIN_SCORES[2]);
+        assert_eq!(game.last_clear.unwrap().name(), "T-SPIN DOUBLE");
+    }
+
+    #[test]
+    fn t_that_only_moved_in_is_no_spin() {
+        let mut game = t_spin_double_ready();
+        game.last_kick = None;
+        assert_eq!(game.t_spin(), Spin::None);
+        game.lock_mino();
+        assert_eq!(game.current_score, GUIDELINE_SCORES[2]);
+    }
+
+    #[test]
+    fn t_spin_with_an_open_front_corner_is_a_mini() {
+        let mut game = playing_with(T_BLOCK, 0, (8, 10));
+        game.scoring = Scoring::Guideline;
+        fill(&mut game, &[(4, 9), (6, 9), (4, 11)]);
+        game.last_kick = Some(0);
+        assert_eq!(game.t_spin(), Spin::Mini);
+        game.lock_mino();
+        assert_eq!(game.current_score, TSPIN_MINI_SCORES[0]);
+    }
+
+    #[test]
+    fn nes_scoring_multiplies_by_the_level() {
+        let mut game = tetris_ready(Scoring::Classic, true);
+        game.ruleset = Ruleset::Nes;
+        game.current_level = 9;
+        game.lock_mino();
+        assert_eq!(game.current_score, BASE_SCORES[4] * 10);
+    }
+
+    #[test]
+    fn push_down_points_last_until_the_mino_locks() {
+        let mut game = Game::new(1);
+        game.set_ruleset(Ruleset::Nes);
+        game.start_game();
+        //a terminal without key releases lets go in the same frame it presses
+        (0..3).for_each(|_| game.step(&[Input::Press(Action::SoftDrop), Input::Release(Action::SoftDrop)]));
+        assert_eq!(game.current_score, 0);
+        game.lock_mino();
+        assert_eq!(game.current_score, 3);
+    }
+
+    #[test]
+    fn switching_rules_keeps_the_overrides() {
+        let mut game = Game::new(1);
+        game.overrides = Overrides { arr: Some(0), next_count: Some(6), ..Overrides::default() };
+        game.set_ruleset(Ruleset::Nes);
+        assert_eq!((game.handling.das, game.handling.arr, game.next_count), (NES_DAS_FRAMES, 0, 6));
+        assert_eq!(game.randomizer.kind(), RandomizerKind::NesReroll);
+        game.set_ruleset(Ruleset::Standard);
+        assert_eq!((game.handling.das, game.handling.arr, game.next_count), (DEFAULT_DAS_FRAMES, 0, 6));
+        assert_eq!(game.randomizer.kind(), DEFAULT_RANDOMIZER);
+    }
+
+    #[test]
+    fn kicks_follow_the_srs_tables() {
+        //spawn is srs state 2, so turning right is 2->L and left is 2->R
+        let t = Mino::new(T_BLOCK);
+        assert_eq!(t.kicks(ROT_RIGHT), vec![(0, 0), (2, 0), (2, -1), (0, 2), (2, 2)]);
+        assert_eq!(t.kicks(ROT_LEFT), vec![(0, 0), (-2, 0), (-2, -1), (0, 2), (-2, 2)]);
+        let i = Mino::new(I_BLOCK);
+        assert_eq!(i.kicks(ROT_RIGHT), vec![(0, 0), (4, 0), (-2, 0), (4, -1), (-2, 2)]);
+        assert_eq!(Mino::new(O_BLOCK).kicks(ROT_RIGHT), vec![NO_OFFSET]);
+    }
+
+    #[test]
+    fn rotating_into_the_wall_kicks_out() {
+        //a t pointing right against the left wall can't turn flat in place, the second test moves it one over
+        let mut game = playing_with(T_BLOCK, 3, (-2, 10));
+        assert!(!game.collision(NO_OFFSET, game.current_mino.get_rotation()));
+        game.rotate_mino(ROT_RIGHT);
+        assert_eq!(game.current_mino.current_rotation, 0);
+        assert_eq!(game.current_mino_position, (0, 10));
+        assert_eq!(game.last_kick, Some(1));
+    }
+
+    #[test]
+    fn cells_above_the_board_are_open() {
+        let mut game = playing_with(T_BLOCK, 2, (8, -1));
+        game.board_state[0] = vec![J_BLOCK; GAME_BOARD_WIDTH];
+        assert!(!game.collision(NO_OFFSET, game.current_mino.get_rotation()));
+        assert!(game.collision(DOWN_OFFSET, game.current_mino.get_rotation()));
+    }
+
+    #[test]
+    fn locking_above_the_board_tops_out() {
+        let mut game = playing_with(T_BLOCK, 2, (8, -1));
+        game.board_state[0] = vec![J_BLOCK; GAME_BOARD_WIDTH];
+        game.lock_mino();
+        assert_eq!(game.game_state, GameState::GameOver);
+    }
+
+    #[test]
+    fn suspended_game_carries_on_the_same() {
+        let mut game = Game::new(8);
+        game.start_game();
+        (0..150).for_each(|frame| game.step(&scripted_inputs(frame)));
+        let snapshot = Snapshot::from_text(&game.suspend().unwrap().to_text()).unwrap();
+
+        let mut resumed = Game::new(1);
+        resumed.resume(snapshot).unwrap();
+        assert_eq!(resumed.game_state, GameState::Paused);
+        for frame in 150..300 {
+            let mut inputs = scripted_inputs(frame);
+            if frame == 150 { inputs.insert(0, Input::Pause); }
+            game.step(&inputs);
+            resumed.step(&inputs);
+        }
+        assert_eq!(resumed.board_state, game.board_state);
+        assert_eq!(resumed.current_mino_position, game.current_mino_position);
+        assert_eq!(resumed.current_score, game.current_score);
+        assert_eq!(resumed.statistics, game.statistics);
+        assert_eq!(resumed.game_state, game.game_state);
+    }
+
+    #[test]
+    fn suspended_mino_off_or_over_the_board_is_refused() {
+        let snapshot = || playing_with(T_BLOCK, 0, (8, 10)).suspend().unwrap();
+        assert!(Snapshot::from_text(&snapshot().to_text()).is_ok());
+
+        let mut off_board = snapshot();
+        off_board.position = (40, 30);
+        assert!(Snapshot::from_text(&off_board.to_text()).is_err());
+        let mut game = Game::new(1);
+        assert!(game.resume(off_board).is_err());
+        assert_eq!(game.game_state, GameState::StartScreen);
+
+        let mut overlapping = snapshot();
+        overlapping.board[11][5] = J_BLOCK;
+        assert!(Snapshot::from_text(&overlapping.to_text()).is_err());
+        assert!(Game::new(1).resume(overlapping).is_err());
+    }
+
+    #[test]
+    fn suspended_counters_past_their_limits_are_refused() {
+        let snapshot = || playing_with(T_BLOCK, 0, (8, 10)).suspend().unwrap();
+        let refused = |snapshot: Snapshot| Snapshot::from_text(&snapshot.to_text()).is_err() && Game::new(1).resume(snapshot).is_err();
+        let mut at_limits = snapshot();
+        (at_limits.gravity_counter, at_limits.lock_timer, at_limits.lock_resets) = (GRAVITY_TABLE[0], Some(LOCK_DELAY_FRAMES), LOCK_RESET_LIMIT);
+        assert!(Snapshot::from_text(&at_limits.to_text()).is_ok());
+
+        let mut gravity = snapshot();
+        gravity.gravity_counter = u8::MAX;
+        assert!(refused(gravity));
+        let mut lock_timer = snapshot();
+        lock_timer.lock_timer = Some(u16::MAX);
+        assert!(refused(lock_timer));
+        let mut lock_resets = snapshot();
+        lock_resets.lock_resets = LOCK_RESET_LIMIT + 1;
+        assert!(refused(lock_resets));
+        let mut combo = snapshot();
+        combo.combo = Some(u16::MAX);
+        assert!(refused(combo));
+        let mut rows = snapshot();
+        rows.rows_cleared = vec![19, 19];
+        assert!(refused(rows));
+        let mut sdf = snapshot();
+        sdf.handling.soft_drop_factor = 0;
+        assert!(refused(sdf));
+    }
+
+    #[test]
+    fn hold_is_refused_until_the_mino_locks() {
+        let mut game = Game::new(3);
+        game.start_game();
+        let (first, second) = (game.current_mino.selected_mino, game.next_queue[0].selected_mino);
+        game.hold();
+        assert_eq!(game.hold_mino.as_ref().map(|mino| mino.selected_mino), Some(first));
+        assert_eq!(game.current_mino.selected_mino, second);
+        //a second swap before locking does nothing
+        game.hold();
+        assert_eq!(game.hold_mino.as_ref().map(|mino| mino.selected_mino), Some(first));
+        assert_eq!(game.current_mino.selected_mino, second);
+
+        game.slam();
+        assert!(!game.hold_used);
+        let third = game.current_mino.selected_mino;
+        game.hold();
+        assert_eq!(game.current_mino.selected_mino, first);
+        assert_eq!(game.current_mino_position, game.current_mino.start_offset);
+        assert_eq!(game.hold_mino.as_ref().map(|mino| mino.selected_mino), Some(third));
+    }
+
+    //the ids of the minos waiting in the queue, front first
+    fn queued(game: &Game) -> Vec<u8> {
+        game.next_queue.iter().map(|mino| mino.selected_mino).collect()
+    }
+
+    #[test]
+    fn preview_length_is_clamped_and_never_changes_the_order() {
+        let mut game = Game::new(4);
+        let upcoming = queued(&game);
+        assert_eq!(game.next_count, DEFAULT_NEXT_COUNT);
+        game.set_next_count(0);
+        assert_eq!(game.next_count, 1);
+        game.set_next_count(99);
+        assert_eq!(game.next_count, MAX_NEXT_COUNT);
+        assert_eq!(queued(&game), upcoming);
+
+        //the queue stays full as minos are dealt from the front of it
+        game.start_game();
+        game.slam();
+        assert_eq!(game.current_mino.selected_mino, upcoming[0]);
+        assert_eq!(game.next_queue.len(), MAX_NEXT_COUNT);
+        assert_eq!(queued(&game)[..MAX_NEXT_COUNT - 1], upcoming[1..]);
+    }
+
+    #[test]
+    fn lock_delay_resets_stop_at_the_limit() {
+        let mut game = playing_with(T_BLOCK, 0, (8, 18));
+        let wait = |game: &mut Game, frames| (0..frames).for_each(|_| game.step(&[]));
+        //shuffling along the floor keeps the mino from locking, but only so many times
+        for reset in 0..LOCK_RESET_LIMIT {
+            wait(&mut game, 10);
+            if reset % 2 == 0 { game.move_left(); } else { game.move_right(); }
+            assert_eq!(game.lock_timer, Some(0));
+        }
+        assert_eq!(game.lock_resets, LOCK_RESET_LIMIT);
+        wait(&mut game, 10);
+        game.move_left();
+        assert_eq!(game.lock_timer, Some(10));
+
+        wait(&mut game, LOCK_DELAY_FRAMES - 11);
+        assert!(game.board_state.iter().flatten().all(|cell| *cell == 0));
+        wait(&mut game, 1);
+        assert_eq!(game.board_state[18][2..5], [T_BLOCK; 3]);
+    }
+
+    #[test]
+    fn held_shift_waits_for_das_then_repeats_every_arr() {
+        let mut game = playing_with(T_BLOCK, 0, (0, 5));
+        game.handling = Handling { das: 10, arr: 2, soft_drop_factor: 1 };
+        //one move on the press, the next when das runs out, then one every arr frames until letting go
+        let mut positions = vec![];
+        game.step(&[Input::Press(Action::MoveRight)]);
+        positions.push(game.current_mino_position.0);
+        for _ in 1..15 {
+            game.step(&[]);
+            positions.push(game.current_mino_position.0);
+        }
+        assert_eq!(positions, [2, 2, 2, 2, 2, 2, 2, 2, 2, 4, 4, 6, 6, 8, 8]);
+        game.step(&[Input::Release(Action::MoveRight)]);
+        (0..10).for_each(|_| game.step(&[]));
+        assert_eq!(game.current_mino_position.0, 8);
+
+        //no arr at all slides straight to the wall
+        game.handling.arr = 0;
+        game.step(&[Input::Press(Action::MoveLeft)]);
+        (0..10).for_each(|_| game.step(&[]));
+        assert_eq!(game.current_mino_position.0, 0);
+    }
+
+    #[test]
+    fn presses_during_the_clear_wait_for_the_next_mino() {
+        let mut game = tetris_ready(Scoring::Classic, false);
+        game.slam();
+        assert_eq!(game.rows_cleared, vec![16, 17, 18, 19]);
+        let locked_at = game.current_mino_position;
+        game.step(&[Input::Press(Action::MoveLeft), Input::Release(Action::MoveLeft)]);
+        assert_eq!(game.current_mino_position, locked_at);
+        assert_eq!(game.board_state[19], vec![J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, J_BLOCK, I_BLOCK]);
+
+        //the rows only drop once the animation has run, and the new mino comes in already moved
+        finish_clear(&mut game);
+        assert!(game.board_state.iter().flatten().all(|cell| *cell == 0));
+        let start = game.current_mino.start_offset;
+        assert_eq!(game.current_mino_position, (start.0 + LEFT_OFFSET.0, start.1));
+    }
+
+    //a tetris ready to go in a sprint that's already this many lines in
+    fn sprint_tetris(lines: u16) -> Game {
+        let mut game = tetris_ready(Scoring::Classic, true);
+        game.mode = GameMode::Sprint;
+        game.line_count = lines;
+        game.play_frames = 600;
+        game
+    }
+
+    #[test]
+    fn sprint_takes_a_split_every_ten_lines() {
+        let mut game = sprint_tetris(28);
+        game.slam();
+        assert_eq!(game.splits, vec![600]);
+        assert_eq!(game.game_state, GameState::Playing);
+        assert!(!game.rows_cleared.is_empty());
+    }
+
+    #[test]
+    fn sprint_finishes_on_the_fortieth_line_with_the_rows_gone() {
+        let mut game = sprint_tetris(38);
+        game.best_splits = vec![700];
+        game.slam();
+        assert_eq!(game.line_count, 42);
+        assert_eq!(game.splits, vec![600]);
+        assert_eq!(game.game_state, GameState::Finished);
+        assert!(game.is_new_personal_best());
+        //nothing left flashing, and the junk above the tetris has dropped to the floor
+        assert!(game.rows_cleared.is_empty());
+        assert_eq!(game.board_state[19][0], J_BLOCK);
+        assert_eq!(game.board_state.iter().flatten().filter(|cell| **cell != 0).count(), 1);
+        game.step(&[]);
+        assert_eq!(game.game_state, GameState::Finished);
+    }
+
+    #[test]
+    fn ultra_ends_when_the_clock_runs_out() {
+        let mut game = Game::new(1);
+        game.mode = GameMode::Ultra;
+        game.time_limit = 10;
+        game.start_game();
+        //the clock stands still while paused
+        game.step(&[Input::Pause]);
+        (0..20).for_each(|_| game.step(&[]));
+        assert_eq!(game.time_left(), 10);
+
+        game.step(&[Input::Pause]);
+        (0..8).for_each(|_| game.step(&[]));
+        assert_eq!(game.time_left(), 1);
+        assert_eq!(game.game_state, GameState::Playing);
+        game.step(&[]);
+        assert_eq!(game.time_left(), 0);
+        assert_eq!(game.game_state, GameState::Finished);
+        assert!(game.take_replay().is_some());
+    }
+
+    #[test]
+    fn every_step_is_one_frame_and_the_clock_only_runs_in_play() {
+        assert_eq!(frames_from_seconds(1.0), 60);
+        assert_eq!(frames_from_seconds(ULTRA_SECONDS), (ULTRA_SECONDS * FRAME_RATE_NTSC).round() as u64);
+        let mut game = Game::new(1);
+        (0..5).for_each(|_| game.step(&[]));
+        assert_eq!((game.frame, game.play_frames), (5, 0));
+        game.start_game();
+        (0..5).for_each(|_| game.step(&[]));
+        game.step(&[Input::Pause]);
+        (0..5).for_each(|_| game.step(&[]));
+        assert_eq!((game.frame, game.play_frames), (11, 5));
+    }
+
+    #[test]
+    fn subscribers_hear_every_event_in_order() {
+        let heard = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let mut game = playing_with(O_BLOCK, 0, (8, 10));
+        let log = heard.clone();
+        game.subscribe(move |event| log.borrow_mut().push(event));
+        game.step(&[Input::Press(Action::MoveLeft), Input::Release(Action::MoveLeft), Input::Press(Action::HardDrop)]);
+        let heard = heard.borrow();
+        assert_eq!(heard[..3], [
+            GameEvent::Moved { offset: LEFT_OFFSET },
+            GameEvent::Moved { offset: (0, 9) },
+            GameEvent::HardDropped { rows: 9 },
+        ]);
+        assert_eq!(heard[3], GameEvent::Locked { mino: O_BLOCK });
+        assert!(matches!(heard[4], GameEvent::PieceSpawned { .. }));
+        assert_eq!(heard.len(), 5);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::engine::consts::*;
+
+//everything a key can be bound to while playing
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateLeft,
+    RotateRight,
+    Hold,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::RotateLeft,
+        Self::RotateRight,
+        Self::Hold,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::SoftDrop => "move_down",
+            Self::HardDrop => "slam",
+            Self::RotateLeft => "rotate_left",
+            Self::RotateRight => "rotate_right",
+            Self::Hold => "hold",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+//everything a player can do to change the outcome of a game - these are what step takes and what replays store
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Press(Action),
+    Release(Action),
+    Pause,
+}
+
+impl Input {
+    pub fn to_text(self) -> String {
+        match self {
+            Self::Press(action) => format!("press {}", action.name()),
+            Self::Release(action) => format!("release {}", action.name()),
+            Self::Pause => "pause".to_string(),
+        }
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let input = match (words.next()?, words.next()) {
+            ("press", Some(action)) => Self::Press(Action::from_name(action)?),
+            ("release", Some(action)) => Self::Release(Action::from_name(action)?),
+            ("pause", None) => Self::Pause,
+            _ => return None,
+        };
+        words.next().is_none().then_some(input)
+    }
+}
+
+//delayed auto shift and auto repeat rate are counted in frames, soft drop multiplies gravity
+#[derive(Clone, Copy)]
+pub struct Handling {
+    pub das: u16,
+    pub arr: u16,
+    pub soft_drop_factor: u16,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: DEFAULT_DAS_FRAMES,
+            arr: DEFAULT_ARR_FRAMES,
+            soft_drop_factor: DEFAULT_SOFT_DROP_FACTOR,
+        }
+    }
+}
+
+//which repeating keys are down, and how far along their timers are
+#[derive(Default)]
+pub struct HeldKeys {
+    pub left: bool,
+    pub right: bool,
+    pub soft_drop: bool,
+    pub shift: Option<BoardXY>, //the most recently pressed direction wins while both are held
+    pub das_counter: u16,
+    pub arr_counter: u16,
+    pub soft_drop_counter: u16,
+}
+
+impl HeldKeys {
+    pub fn start_shift(&mut self, direction: BoardXY) {
+        self.shift = Some(direction);
+        self.das_counter = 0;
+        self.arr_counter = 0;
+    }
+
+    //letting go of one direction falls back to the other if it's still held, charging das from scratch
+    pub fn release_shift(&mut self, direction: BoardXY) {
+        if self.shift != Some(direction) { return; }
+        if direction == LEFT_OFFSET && self.right {
+            self.start_shift(RIGHT_OFFSET);
+        } else if direction == RIGHT_OFFSET && self.left {
+            self.start_shift(LEFT_OFFSET);
+        } else {
+            self.shift = None;
+        }
+    }
+}
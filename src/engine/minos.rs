@@ -0,0 +1,233 @@
+use crate::engine::consts::*;
+
+#[derive(Clone)]
+pub struct Mino {
+    rotations: Vec<Rotation>,
+    pub selected_mino: u8,
+    pub current_rotation: usize,
+    pub start_offset: BoardXY,
+}
+
+impl Mino {
+
+    pub fn get_rotation(&self) -> &Rotation {
+        &self.rotations[self.current_rotation]
+    }
+
+    pub fn rotation_count(&self) -> usize {
+        self.rotations.len()
+    }
+
+    //index of the rotation a turn in the given direction would land on
+    fn rotation_index(&self, direction: u8) -> usize {
+        let count = self.rotations.len();
+        match direction {
+            ROT_LEFT => (self.current_rotation + count - 1) % count,
+            ROT_RIGHT => (self.current_rotation + 1) % count,
+            _ => self.current_rotation,
+        }
+    }
+
+    pub fn next_rotation(&self, direction: u8) -> &Rotation {
+        &self.rotations[self.rotation_index(direction)]
+    }
+
+    pub fn rotate(&mut self, direction: u8) {
+        self.current_rotation = self.rotation_index(direction);
+    }
+
+    //rotation 0 is the flat side up spawn used by the original game, which is state 2 in srs terms
+    fn srs_state(rotation_index: usize) -> usize {
+        (rotation_index + 2) % 4
+    }
+
+    //srs kick tests for a turn in the given direction, converted into board offsets (x doubled, y pointing down)
+    pub fn kicks(&self, direction: u8) -> Vec<BoardXY> {
+        if self.rotations.len() < 4 {
+            return vec![NO_OFFSET];
+        }
+        let from = Self::srs_state(self.current_rotation);
+        let transition = from * 2 + if direction == ROT_LEFT { 1 } else { 0 };
+        let table = if self.selected_mino == I_BLOCK { &SRS_KICKS_I } else { &SRS_KICKS_JLSTZ };
+        table[transition].iter().map(|(x, y)| (x * 2, -y)).collect()
+    }
+
+    pub fn new(selected_mino: u8) -> Self {
+        let mut rotations: Vec<Rotation> = vec![];
+        let mut start_offset: BoardXY = (0, 0);
+        //FIX: this should use constant arrays instead of vectors built like this
+        match selected_mino {
+            T_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0],
+                        vec![1, 1, 1],
+                        vec![0, 1, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![1, 1, 0],
+                        vec![0, 1, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![0, 1, 1],
+                        vec![0, 1, 0],
+                    ],
+                ];
+                start_offset = (8, 0);
+            },
+            J_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0],
+                        vec![1, 1, 1],
+                        vec![0, 0, 1],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![0, 1, 0],
+                        vec![1, 1, 0],
+                    ],
+                    vec![
+                        vec![1, 0, 0],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 1],
+                        vec![0, 1, 0],
+                        vec![0, 1, 0],
+                    ],
+                ];
+                start_offset = (8, 0);
+            },
+            Z_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0],
+                        vec![0, 1, 1],
+                        vec![1, 1, 0],
+                    ],
+                    vec![
+                        vec![1, 0, 0],
+                        vec![1, 1, 0],
+                        vec![0, 1, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 1],
+                        vec![1, 1, 0],
+                        vec![0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![0, 1, 1],
+                        vec![0, 0, 1],
+                    ],
+                ];
+                start_offset = (8, 0);
+            },
+            O_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![1, 1],
+                        vec![1, 1],
+                    ],
+                ];
+                start_offset = (8, 1);
+            },
+            S_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0],
+                        vec![1, 1, 0],
+                        vec![0, 1, 1],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![1, 1, 0],
+                        vec![1, 0, 0],
+                    ],
+                    vec![
+                        vec![1, 1, 0],
+                        vec![0, 1, 1],
+                        vec![0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 0, 1],
+                        vec![0, 1, 1],
+                        vec![0, 1, 0],
+                    ],
+                ];
+                //was 9
+                start_offset = (8, 0);
+            },
+            L_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0],
+                        vec![1, 1, 1],
+                        vec![1, 0, 0],
+                    ],
+                    vec![
+                        vec![1, 1, 0],
+                        vec![0, 1, 0],
+                        vec![0, 1, 0],
+                    ],
+                    vec![
+                        vec![0, 0, 1],
+                        vec![1, 1, 1],
+                        vec![0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0],
+                        vec![0, 1, 0],
+                        vec![0, 1, 1],
+                    ],
+                ];
+                start_offset = (8, 0);
+            },
+            I_BLOCK => {
+                rotations = vec![
+                    vec![
+                        vec![0, 0, 0, 0],
+                        vec![0, 0, 0, 0],
+                        vec![1, 1, 1, 1],
+                        vec![0, 0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 1, 0, 0],
+                        vec![0, 1, 0, 0],
+                        vec![0, 1, 0, 0],
+                        vec![0, 1, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 0, 0, 0],
+                        vec![1, 1, 1, 1],
+                        vec![0, 0, 0, 0],
+                        vec![0, 0, 0, 0],
+                    ],
+                    vec![
+                        vec![0, 0, 1, 0],
+                        vec![0, 0, 1, 0],
+                        vec![0, 0, 1, 0],
+                        vec![0, 0, 1, 0],
+                    ],
+                ];
+                start_offset = (6, -1);
+            },
+            _ => {},
+        }
+        Self {
+            rotations,
+            selected_mino,
+            current_rotation: 0,
+            start_offset,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+//a pure, frame stepped game - feed it inputs once per frame and read the state back out
+pub mod consts;
+pub mod event;
+pub mod game;
+pub mod input;
+pub mod minos;
+pub mod mode;
+pub mod randomizer;
+pub mod replay;
+pub mod ruleset;
+pub mod scoring;
+pub mod snapshot;
+
+pub use event::{EventSubscriber, GameEvent, GameState};
+pub use game::Game;
+pub use input::{Action, Handling, Input};
+pub use mode::GameMode;
+pub use randomizer::RandomizerKind;
+pub use replay::Replay;
+pub use ruleset::{Overrides, Ruleset};
+pub use scoring::Scoring;
+pub use snapshot::Snapshot;
@@ -0,0 +1,35 @@
+//what a game is played for - marathon runs until the stack tops out, sprint races to a line goal, ultra scores against the clock
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Marathon,
+    Sprint,
+    Ultra,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [Self::Marathon, Self::Sprint, Self::Ultra];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Marathon => "marathon",
+            Self::Sprint => "sprint",
+            Self::Ultra => "ultra",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    //the mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    //the mode before this one, wrapping around
+    pub fn previous(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or_default();
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
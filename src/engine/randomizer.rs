@@ -0,0 +1,311 @@
+use crate::engine::consts::*;
+
+//splitmix64 - tiny, fast, and the same sequence for a seed on every platform and build
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    //value in 0..bound
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    //a random mino id in 1..=MINO_TYPES
+    pub fn mino(&mut self) -> u8 {
+        self.below(MINO_TYPES as u64) as u8 + 1
+    }
+}
+
+pub trait Randomizer {
+    fn kind(&self) -> RandomizerKind;
+    fn seed(&self) -> u64;
+    fn next_mino(&mut self) -> u8;
+    //everything needed to carry on dealing where it left off, the rng first
+    fn state(&self) -> Vec<u64>;
+    //put back a state from state() - false if it doesn't belong to this generator
+    fn set_state(&mut self, state: &[u64]) -> bool;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RandomizerKind {
+    SevenBag,
+    NesReroll,
+    Tgm,
+    PureRandom,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 4] = [Self::SevenBag, Self::NesReroll, Self::Tgm, Self::PureRandom];
+
+    pub fn create(self, seed: u64) -> Box<dyn Randomizer + Send> {
+        match self {
+            Self::SevenBag => Box::new(SevenBag::new(seed)),
+            Self::NesReroll => Box::new(NesReroll::new(seed)),
+            Self::Tgm => Box::new(Tgm::new(seed)),
+            Self::PureRandom => Box::new(PureRandom::new(seed)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SevenBag => "bag",
+            Self::NesReroll => "nes",
+            Self::Tgm => "tgm",
+            Self::PureRandom => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+//every mino once per bag of seven, in shuffled order
+pub struct SevenBag {
+    seed: u64,
+    rng: Rng,
+    bag: Vec<u8>,
+}
+
+impl SevenBag {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: Rng::new(seed), bag: vec![] }
+    }
+}
+
+impl Randomizer for SevenBag {
+    fn kind(&self) -> RandomizerKind { RandomizerKind::SevenBag }
+    fn seed(&self) -> u64 { self.seed }
+
+    fn next_mino(&mut self) -> u8 {
+        if self.bag.is_empty() {
+            self.bag = (1..=MINO_TYPES).collect();
+            //fisher-yates
+            (1..self.bag.len()).rev().for_each(|index| {
+                let swap = self.rng.below(index as u64 + 1) as usize;
+                self.bag.swap(index, swap);
+            });
+        }
+        self.bag.pop().unwrap()
+    }
+
+    fn state(&self) -> Vec<u64> {
+        let mut state = vec![self.rng.state()];
+        state.extend(self.bag.iter().map(|mino| *mino as u64));
+        state
+    }
+
+    fn set_state(&mut self, state: &[u64]) -> bool {
+        let Some((rng, bag)) = state.split_first() else { return false };
+        if bag.len() > MINO_TYPES as usize || !bag.iter().all(|mino| is_mino(*mino)) { return false; }
+        self.rng = Rng::new(*rng);
+        self.bag = bag.iter().map(|mino| *mino as u8).collect();
+        true
+    }
+}
+
+//roll one of eight values, and if that's the extra value or a repeat of the last mino roll once more from the seven
+pub struct NesReroll {
+    seed: u64,
+    rng: Rng,
+    last: u8,
+}
+
+impl NesReroll {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: Rng::new(seed), last: 0 }
+    }
+}
+
+impl Randomizer for NesReroll {
+    fn kind(&self) -> RandomizerKind { RandomizerKind::NesReroll }
+    fn seed(&self) -> u64 { self.seed }
+
+    fn next_mino(&mut self) -> u8 {
+        let roll = self.rng.below(MINO_TYPES as u64 + 1) as u8 + 1;
+        let mino = if roll > MINO_TYPES || roll == self.last { self.rng.mino() } else { roll };
+        self.last = mino;
+        mino
+    }
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.rng.state(), self.last as u64]
+    }
+
+    fn set_state(&mut self, state: &[u64]) -> bool {
+        //nothing has been dealt yet while the last mino is 0
+        let [rng, last] = *state else { return false };
+        if last != 0 && !is_mino(last) { return false; }
+        self.rng = Rng::new(rng);
+        self.last = last as u8;
+        true
+    }
+}
+
+//tgm keeps the last four minos and tries up to four rolls to avoid them, the first mino is never an S, Z or O
+pub struct Tgm {
+    seed: u64,
+    rng: Rng,
+    history: [u8; TGM_HISTORY_LENGTH],
+    first: bool,
+}
+
+impl Tgm {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: Rng::new(seed), history: [Z_BLOCK; TGM_HISTORY_LENGTH], first: true }
+    }
+}
+
+impl Randomizer for Tgm {
+    fn kind(&self) -> RandomizerKind { RandomizerKind::Tgm }
+    fn seed(&self) -> u64 { self.seed }
+
+    fn next_mino(&mut self) -> u8 {
+        let mino = if self.first {
+            self.first = false;
+            let mut mino = self.rng.mino();
+            while [S_BLOCK, Z_BLOCK, O_BLOCK].contains(&mino) {
+                mino = self.rng.mino();
+            }
+            mino
+        } else {
+            let mut mino = self.rng.mino();
+            for _ in 1..TGM_TRIES {
+                if !self.history.contains(&mino) { break; }
+                mino = self.rng.mino();
+            }
+            mino
+        };
+        self.history.rotate_right(1);
+        self.history[0] = mino;
+        mino
+    }
+
+    fn state(&self) -> Vec<u64> {
+        let mut state = vec![self.rng.state(), self.first as u64];
+        state.extend(self.history.iter().map(|mino| *mino as u64));
+        state
+    }
+
+    fn set_state(&mut self, state: &[u64]) -> bool {
+        let [rng, first, ref history @ ..] = *state else { return false };
+        if first > 1 || history.len() != TGM_HISTORY_LENGTH || !history.iter().all(|mino| is_mino(*mino)) { return false; }
+        self.rng = Rng::new(rng);
+        self.first = first == 1;
+        history.iter().enumerate().for_each(|(index, mino)| self.history[index] = *mino as u8);
+        true
+    }
+}
+
+//no memory at all, floods and droughts included
+pub struct PureRandom {
+    seed: u64,
+    rng: Rng,
+}
+
+impl PureRandom {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: Rng::new(seed) }
+    }
+}
+
+impl Randomizer for PureRandom {
+    fn kind(&self) -> RandomizerKind { RandomizerKind::PureRandom }
+    fn seed(&self) -> u64 { self.seed }
+
+    fn next_mino(&mut self) -> u8 {
+        self.rng.mino()
+    }
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.rng.state()]
+    }
+
+    fn set_state(&mut self, state: &[u64]) -> bool {
+        let [rng] = *state else { return false };
+        self.rng = Rng::new(rng);
+        true
+    }
+}
+
+fn is_mino(value: u64) -> bool {
+    (1..=MINO_TYPES as u64).contains(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deal(randomizer: &mut dyn Randomizer, count: usize) -> Vec<u8> {
+        (0..count).map(|_| randomizer.next_mino()).collect()
+    }
+
+    #[test]
+    fn rng_matches_splitmix64() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_minos() {
+        for kind in RandomizerKind::ALL {
+            let dealt = deal(kind.create(42).as_mut(), 100);
+            assert_eq!(dealt, deal(kind.create(42).as_mut(), 100), "{}", kind.name());
+            assert_ne!(dealt, deal(kind.create(43).as_mut(), 100), "{}", kind.name());
+            assert!(dealt.iter().all(|mino| is_mino(*mino as u64)), "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn restored_state_carries_on_dealing() {
+        for kind in RandomizerKind::ALL {
+            let mut randomizer = kind.create(7);
+            deal(randomizer.as_mut(), 10);
+            let mut restored = kind.create(7);
+            assert!(restored.set_state(&randomizer.state()), "{}", kind.name());
+            assert_eq!(deal(randomizer.as_mut(), 50), deal(restored.as_mut(), 50), "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn bag_deals_every_mino_once_per_seven() {
+        let dealt = deal(&mut SevenBag::new(3), 70);
+        dealt.chunks(MINO_TYPES as usize).for_each(|bag| {
+            let mut bag = bag.to_vec();
+            bag.sort();
+            assert_eq!(bag, (1..=MINO_TYPES).collect::<Vec<u8>>());
+        });
+    }
+
+    #[test]
+    fn tgm_never_starts_with_s_z_or_o() {
+        for seed in 0..200 {
+            assert!(![S_BLOCK, Z_BLOCK, O_BLOCK].contains(&Tgm::new(seed).next_mino()));
+        }
+    }
+
+    #[test]
+    fn state_from_another_generator_is_refused() {
+        assert!(!NesReroll::new(1).set_state(&Tgm::new(1).state()));
+        assert!(!SevenBag::new(1).set_state(&[1, 9]));
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+
+use crate::engine::consts::*;
+use crate::engine::game::frames_from_seconds;
+use crate::engine::input::{Handling, Input};
+use crate::engine::mode::GameMode;
+use crate::engine::randomizer::RandomizerKind;
+use crate::engine::ruleset::Ruleset;
+use crate::engine::scoring::Scoring;
+
+//a game boils down to its mode and rules, the piece generator it was dealt from, the handling it was played with, and its inputs
+pub struct Replay {
+    pub mode: GameMode,
+    pub time_limit: u64,
+    pub scoring: Scoring,
+    pub ruleset: Ruleset,
+    pub start_level: u8,
+    pub randomizer: RandomizerKind,
+    pub seed: u64,
+    pub handling: Handling,
+    pub inputs: VecDeque<(u64, Input)>,
+}
+
+impl Replay {
+    pub fn new(mode: GameMode, time_limit: u64, randomizer: RandomizerKind, seed: u64, handling: Handling) -> Self {
+        Self { mode, time_limit, scoring: Scoring::Classic, ruleset: Ruleset::Standard, start_level: 0, randomizer, seed, handling, inputs: VecDeque::new() }
+    }
+
+    pub fn record(&mut self, frame: u64, input: Input) {
+        self.inputs.push_back((frame, input));
+    }
+
+    //pull the next input if it belongs to this frame
+    pub fn next_input(&mut self, frame: u64) -> Option<Input> {
+        let (input_frame, input) = *self.inputs.front()?;
+        if input_frame != frame { return None; }
+        self.inputs.pop_front();
+        Some(input)
+    }
+
+    //the plain text form of a replay, one setting or input per line - where it gets written is up to the front end
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        //writing into a string can't fail
+        let _ = writeln!(text, "{REPLAY_MAGIC} {REPLAY_VERSION}");
+        let _ = writeln!(text, "mode {}", self.mode.name());
+        let _ = writeln!(text, "time_limit {}", self.time_limit);
+        let _ = writeln!(text, "scoring {}", self.scoring.name());
+        let _ = writeln!(text, "ruleset {}", self.ruleset.name());
+        let _ = writeln!(text, "start_level {}", self.start_level);
+        let _ = writeln!(text, "randomizer {}", self.randomizer.name());
+        let _ = writeln!(text, "seed {}", self.seed);
+        let _ = writeln!(text, "das {}", self.handling.das);
+        let _ = writeln!(text, "arr {}", self.handling.arr);
+        let _ = writeln!(text, "sdf {}", self.handling.soft_drop_factor);
+        for (frame, input) in &self.inputs {
+            let _ = writeln!(text, "{frame} {}", input.to_text());
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let invalid = |line: usize, message: &str| ReplayError { line, message: message.to_string() };
+
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+        let (_, header) = lines.next().ok_or_else(|| invalid(1, "empty replay file"))?;
+        match header.strip_prefix(REPLAY_MAGIC).map(|version| version.trim().parse::<u32>()) {
+            Some(Ok(REPLAY_VERSION)) => {},
+            Some(Ok(version)) => return Err(invalid(1, &format!("replay version {version} isn't supported, expected {REPLAY_VERSION}"))),
+            _ => return Err(invalid(1, "not a rata-tetris replay")),
+        }
+
+        //to_text writes every setting, so one that's missing means the file was cut short or edited - playing it would come out different
+        let mut replay = Self::new(GameMode::Marathon, frames_from_seconds(ULTRA_SECONDS), DEFAULT_RANDOMIZER, 0, Handling::default());
+        let mut missing = REPLAY_SETTINGS.to_vec();
+        let mut last_line = 1;
+        for (number, line) in lines {
+            last_line = number;
+            let (key, value) = line.split_once(' ').ok_or_else(|| invalid(number, "expected a key and a value"))?;
+            missing.retain(|setting| *setting != key);
+            let bad_value = || invalid(number, &format!("bad value for {key}: {value}"));
+            match key {
+                "mode" => replay.mode = GameMode::from_name(value).ok_or_else(bad_value)?,
+                "scoring" => replay.scoring = Scoring::from_name(value).ok_or_else(bad_value)?,
+                "ruleset" => replay.ruleset = Ruleset::from_name(value).ok_or_else(bad_value)?,
+                "start_level" => replay.start_level = value.parse().map_err(|_| bad_value())?,
+                "time_limit" => replay.time_limit = value.parse().map_err(|_| bad_value())?,
+                "randomizer" => replay.randomizer = RandomizerKind::from_name(value).ok_or_else(bad_value)?,
+                "seed" => replay.seed = value.parse().map_err(|_| bad_value())?,
+                "das" => replay.handling.das = value.parse().map_err(|_| bad_value())?,
+                "arr" => replay.handling.arr = value.parse().map_err(|_| bad_value())?,
+                "sdf" => replay.handling.soft_drop_factor = value.parse().map_err(|_| bad_value())?,
+                frame => {
+                    let frame = frame.parse().map_err(|_| invalid(number, &format!("unknown entry: {key}")))?;
+                    let input = Input::from_text(value).ok_or_else(|| invalid(number, &format!("unknown input: {value}")))?;
+                    replay.record(frame, input);
+                }
+            }
+        }
+        if let Some(setting) = missing.first() {
+            return Err(invalid(last_line, &format!("replay has no {setting} line")));
+        }
+        Ok(replay)
+    }
+}
+
+//what went wrong reading a replay, and on which line
+#[derive(Debug)]
+pub struct ReplayError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::input::Action;
+
+    fn sample() -> Replay {
+        let handling = Handling { das: 10, arr: 0, soft_drop_factor: 40 };
+        let mut replay = Replay::new(GameMode::Ultra, 3600, RandomizerKind::Tgm, 12345, handling);
+        replay.scoring = Scoring::Guideline;
+        replay.ruleset = Ruleset::Nes;
+        replay.start_level = 9;
+        replay.record(3, Input::Press(Action::MoveLeft));
+        replay.record(9, Input::Release(Action::MoveLeft));
+        replay.record(9, Input::Pause);
+        replay
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let text = sample().to_text();
+        let replay = Replay::from_text(&text).unwrap();
+        assert_eq!(replay.to_text(), text);
+        assert_eq!(replay.mode, GameMode::Ultra);
+        assert_eq!(replay.ruleset, Ruleset::Nes);
+        assert_eq!(replay.seed, 12345);
+        assert_eq!(replay.handling.arr, 0);
+        assert_eq!(replay.inputs, sample().inputs);
+    }
+
+    #[test]
+    fn missing_settings_are_refused() {
+        let text = sample().to_text().replace("seed 12345\n", "");
+        assert!(Replay::from_text(&text).err().unwrap().message.contains("seed"));
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let text = sample().to_text().replacen(&REPLAY_VERSION.to_string(), "2", 1);
+        assert_eq!(Replay::from_text(&text).err().unwrap().line, 1);
+        assert!(Replay::from_text("not a replay").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::engine::consts::*;
+use crate::engine::input::Handling;
+use crate::engine::randomizer::RandomizerKind;
+
+//how the pieces behave - standard is the modern feel, nes plays like the original cartridge
+//nes means reroll pieces, one preview, no hold, hard drop, ghost or kicks, locking the moment a mino lands,
+//16/6 das that charges against walls, push down points, and the nes level transitions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ruleset {
+    Standard,
+    Nes,
+}
+
+impl Ruleset {
+    pub const ALL: [Ruleset; 2] = [Self::Standard, Self::Nes];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Nes => "nes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ruleset| ruleset.name() == name)
+    }
+
+    //the ruleset after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|ruleset| *ruleset == self).unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn randomizer(self) -> RandomizerKind {
+        match self {
+            Self::Standard => DEFAULT_RANDOMIZER,
+            Self::Nes => RandomizerKind::NesReroll,
+        }
+    }
+
+    pub fn handling(self) -> Handling {
+        match self {
+            Self::Standard => Handling::default(),
+            Self::Nes => Handling { das: NES_DAS_FRAMES, arr: NES_ARR_FRAMES, ..Handling::default() },
+        }
+    }
+
+    pub fn next_count(self) -> usize {
+        match self {
+            Self::Standard => DEFAULT_NEXT_COUNT,
+            Self::Nes => 1,
+        }
+    }
+
+    //the level reached after clearing this many lines from a starting level
+    pub fn level_for_lines(self, start_level: u8, lines: u16) -> u8 {
+        match self {
+            Self::Standard => start_level.max((lines / 10).min(u8::MAX as u16) as u8),
+            Self::Nes => {
+                //higher starting levels hold on for longer before the first level up, every 10 lines after that
+                let start = start_level as u16;
+                let first = (start * 10 + 10).min((start * 10).saturating_sub(50).max(100));
+                if lines < first { return start_level; }
+                (start + 1 + (lines - first) / 10).min(u8::MAX as u16) as u8
+            },
+        }
+    }
+}
+
+//what the player picked for themselves, kept apart from the ruleset so switching rules brings its own defaults but never loses these
+#[derive(Clone, Copy, Default)]
+pub struct Overrides {
+    pub das: Option<u16>,
+    pub arr: Option<u16>,
+    pub soft_drop_factor: Option<u16>,
+    pub next_count: Option<usize>,
+    pub randomizer: Option<RandomizerKind>,
+}
+
+impl Overrides {
+    pub fn handling(&self, ruleset: Ruleset) -> Handling {
+        let defaults = ruleset.handling();
+        Handling {
+            das: self.das.unwrap_or(defaults.das),
+            arr: self.arr.unwrap_or(defaults.arr),
+            soft_drop_factor: self.soft_drop_factor.unwrap_or(defaults.soft_drop_factor),
+        }
+    }
+
+    pub fn next_count(&self, ruleset: Ruleset) -> usize {
+        self.next_count.unwrap_or(ruleset.next_count())
+    }
+
+    pub fn randomizer(&self, ruleset: Ruleset) -> RandomizerKind {
+        self.randomizer.unwrap_or(ruleset.randomizer())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_levels_up_every_ten_lines() {
+        assert_eq!(Ruleset::Standard.level_for_lines(0, 9), 0);
+        assert_eq!(Ruleset::Standard.level_for_lines(0, 10), 1);
+        assert_eq!(Ruleset::Standard.level_for_lines(0, 125), 12);
+        //a high start holds until the lines catch up with it
+        assert_eq!(Ruleset::Standard.level_for_lines(5, 40), 5);
+    }
+
+    #[test]
+    fn nes_levels_follow_the_cartridge() {
+        //lines needed for the first level up from each start, then every 10 after
+        let first = [(0, 10), (5, 60), (9, 100), (10, 100), (15, 100), (16, 110), (18, 130), (19, 140)];
+        for (start, lines) in first {
+            assert_eq!(Ruleset::Nes.level_for_lines(start, lines - 1), start, "start {start}");
+            assert_eq!(Ruleset::Nes.level_for_lines(start, lines), start + 1, "start {start}");
+            assert_eq!(Ruleset::Nes.level_for_lines(start, lines + 10), start + 2, "start {start}");
+        }
+    }
+
+    #[test]
+    fn overrides_win_over_the_ruleset() {
+        let overrides = Overrides { das: Some(8), next_count: Some(5), randomizer: Some(RandomizerKind::Tgm), ..Overrides::default() };
+        let handling = overrides.handling(Ruleset::Nes);
+        assert_eq!((handling.das, handling.arr), (8, NES_ARR_FRAMES));
+        assert_eq!(overrides.next_count(Ruleset::Nes), 5);
+        assert_eq!(overrides.randomizer(Ruleset::Nes), RandomizerKind::Tgm);
+        assert_eq!(Overrides::default().randomizer(Ruleset::Nes), RandomizerKind::NesReroll);
+    }
+}
@@ -0,0 +1,51 @@
+//how points are handed out - classic only pays for lines, guideline adds t-spins, back-to-backs, combos, perfect clears and drops
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scoring {
+    Classic,
+    Guideline,
+}
+
+impl Scoring {
+    pub const ALL: [Scoring; 2] = [Self::Classic, Self::Guideline];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Guideline => "guideline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scoring| scoring.name() == name)
+    }
+}
+
+//a t mino locked straight after a rotation with three of its four corners filled
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+//the last lock that cleared lines or spun, kept around so the front end can call it out
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LineClear {
+    pub lines: usize,
+    pub spin: Spin,
+    pub back_to_back: bool,
+    pub combo: u16,
+    pub perfect: bool,
+}
+
+impl LineClear {
+    pub fn name(&self) -> String {
+        let lines = ["", "SINGLE", "DOUBLE", "TRIPLE", "TETRIS"][self.lines];
+        let name = match self.spin {
+            Spin::None => lines.to_string(),
+            Spin::Mini => format!("T-SPIN MINI {lines}"),
+            Spin::Full => format!("T-SPIN {lines}"),
+        };
+        name.trim_end().to_string()
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+use crate::engine::consts::*;
+use crate::engine::game::{fits, frames_from_seconds};
+use crate::engine::input::{Action, Handling, Input};
+use crate::engine::minos::Mino;
+use crate::engine::mode::GameMode;
+use crate::engine::randomizer::RandomizerKind;
+use crate::engine::ruleset::Ruleset;
+use crate::engine::scoring::Scoring;
+
+//everything about a game in progress, enough to put it down and carry on later exactly where it was left
+pub struct Snapshot {
+    pub mode: GameMode,
+    pub time_limit: u64,
+    pub scoring: Scoring,
+    pub ruleset: Ruleset,
+    pub start_level: u8,
+    pub handling: Handling,
+    pub next_count: usize,
+    pub randomizer: RandomizerKind,
+    pub seed: u64,
+    pub randomizer_state: Vec<u64>,
+    pub seeds: u64, //where the game's own seed generator is at, for the games after this one
+    pub fixed_seed: Option<u64>,
+    pub frame: u64,
+    pub play_frames: u64,
+    pub score: Score,
+    pub level: u8,
+    pub lines: u16,
+    pub statistics: Vec<u16>,
+    pub splits: Vec<u64>,
+    pub board: Vec<Vec<u8>>,
+    pub current_mino: u8,
+    pub rotation: usize,
+    pub position: BoardXY,
+    pub next_queue: Vec<u8>,
+    pub hold_mino: Option<u8>,
+    pub hold_used: bool,
+    pub gravity_counter: u8,
+    pub lock_timer: Option<u16>,
+    pub lock_resets: u8,
+    pub lowest_row: i16,
+    pub last_kick: Option<usize>,
+    pub push_down: u32,
+    pub back_to_back: bool,
+    pub combo: Option<u16>,
+    pub rows_cleared: Vec<usize>,
+    pub clear_frames: u16,
+    pub buffered_actions: Vec<Action>,
+    pub inputs: VecDeque<(u64, Input)>, //the recording so far, so the finished game still leaves a whole replay
+}
+
+impl Snapshot {
+    //the same one setting per line as a replay, with the recorded inputs last
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        //writing into a string can't fail
+        let _ = writeln!(text, "{SNAPSHOT_MAGIC} {SNAPSHOT_VERSION}");
+        let _ = writeln!(text, "mode {}", self.mode.name());
+        let _ = writeln!(text, "time_limit {}", self.time_limit);
+        let _ = writeln!(text, "scoring {}", self.scoring.name());
+        let _ = writeln!(text, "ruleset {}", self.ruleset.name());
+        let _ = writeln!(text, "start_level {}", self.start_level);
+        let _ = writeln!(text, "das {}", self.handling.das);
+        let _ = writeln!(text, "arr {}", self.handling.arr);
+        let _ = writeln!(text, "sdf {}", self.handling.soft_drop_factor);
+        let _ = writeln!(text, "next_count {}", self.next_count);
+        let _ = writeln!(text, "randomizer {}", self.randomizer.name());
+        let _ = writeln!(text, "seed {}", self.seed);
+        let _ = writeln!(text, "randomizer_state {}", list(&self.randomizer_state));
+        let _ = writeln!(text, "seeds {}", self.seeds);
+        let _ = writeln!(text, "fixed_seed {}", optional(self.fixed_seed));
+        let _ = writeln!(text, "frame {}", self.frame);
+        let _ = writeln!(text, "play_frames {}", self.play_frames);
+        let _ = writeln!(text, "score {}", self.score);
+        let _ = writeln!(text, "level {}", self.level);
+        let _ = writeln!(text, "lines {}", self.lines);
+        let _ = writeln!(text, "statistics {}", list(&self.statistics));
+        let _ = writeln!(text, "splits {}", list(&self.splits));
+        for row in &self.board {
+            let _ = writeln!(text, "board {}", row.iter().map(|cell| cell.to_string()).collect::<String>());
+        }
+        let _ = writeln!(text, "current {} {} {} {}", self.current_mino, self.rotation, self.position.0, self.position.1);
+        let _ = writeln!(text, "next {}", list(&self.next_queue));
+        let _ = writeln!(text, "hold {}", optional(self.hold_mino));
+        let _ = writeln!(text, "hold_used {}", self.hold_used);
+        let _ = writeln!(text, "gravity_counter {}", self.gravity_counter);
+        let _ = writeln!(text, "lock_timer {}", optional(self.lock_timer));
+        let _ = writeln!(text, "lock_resets {}", self.lock_resets);
+        let _ = writeln!(text, "lowest_row {}", self.lowest_row);
+        let _ = writeln!(text, "last_kick {}", optional(self.last_kick));
+        let _ = writeln!(text, "push_down {}", self.push_down);
+        let _ = writeln!(text, "back_to_back {}", self.back_to_back);
+        let _ = writeln!(text, "combo {}", optional(self.combo));
+        let _ = writeln!(text, "rows_cleared {}", list(&self.rows_cleared));
+        let _ = writeln!(text, "clear_frames {}", self.clear_frames);
+        let _ = writeln!(text, "buffered {}", self.buffered_actions.iter().map(|action| action.name()).collect::<Vec<&str>>().join(" "));
+        for (frame, input) in &self.inputs {
+            let _ = writeln!(text, "{frame} {}", input.to_text());
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SnapshotError> {
+        let invalid = |line: usize, message: &str| SnapshotError(format!("line {line}: {message}"));
+
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+        let (_, header) = lines.next().ok_or_else(|| invalid(1, "empty snapshot file"))?;
+        match header.strip_prefix(SNAPSHOT_MAGIC).map(|version| version.trim().parse::<u32>()) {
+            Some(Ok(SNAPSHOT_VERSION)) => {},
+            Some(Ok(version)) => return Err(invalid(1, &format!("snapshot version {version} isn't supported, expected {SNAPSHOT_VERSION}"))),
+            _ => return Err(invalid(1, "not a rata-tetris snapshot")),
+        }
+
+        let mut snapshot = Self::blank();
+        for (number, line) in lines {
+            //lists can be empty, leaving nothing after the key
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let bad_value = || invalid(number, &format!("bad value for {key}: {value}"));
+            match key {
+                "mode" => snapshot.mode = GameMode::from_name(value).ok_or_else(bad_value)?,
+                "time_limit" => snapshot.time_limit = value.parse().map_err(|_| bad_value())?,
+                "scoring" => snapshot.scoring = Scoring::from_name(value).ok_or_else(bad_value)?,
+                "ruleset" => snapshot.ruleset = Ruleset::from_name(value).ok_or_else(bad_value)?,
+                "start_level" => snapshot.start_level = value.parse().map_err(|_| bad_value())?,
+                "das" => snapshot.handling.das = value.parse().map_err(|_| bad_value())?,
+                "arr" => snapshot.handling.arr = value.parse().map_err(|_| bad_value())?,
+                "sdf" => snapshot.handling.soft_drop_factor = value.parse().map_err(|_| bad_value())?,
+                "next_count" => snapshot.next_count = value.parse().map_err(|_| bad_value())?,
+                "randomizer" => snapshot.randomizer = RandomizerKind::from_name(value).ok_or_else(bad_value)?,
+                "seed" => snapshot.seed = value.parse().map_err(|_| bad_value())?,
+                "randomizer_state" => snapshot.randomizer_state = parse_list(value).ok_or_else(bad_value)?,
+                "seeds" => snapshot.seeds = value.parse().map_err(|_| bad_value())?,
+                "fixed_seed" => snapshot.fixed_seed = parse_optional(value).ok_or_else(bad_value)?,
+                "frame" => snapshot.frame = value.parse().map_err(|_| bad_value())?,
+                "play_frames" => snapshot.play_frames = value.parse().map_err(|_| bad_value())?,
+                "score" => snapshot.score = value.parse().map_err(|_| bad_value())?,
+                "level" => snapshot.level = value.parse().map_err(|_| bad_value())?,
+                "lines" => snapshot.lines = value.parse().map_err(|_| bad_value())?,
+                "statistics" => snapshot.statistics = parse_list(value).ok_or_else(bad_value)?,
+                "splits" => snapshot.splits = parse_list(value).ok_or_else(bad_value)?,
+                "board" => {
+                    let row = value.chars().map(|cell| cell.to_digit(10).map(|cell| cell as u8)).collect::<Option<Vec<u8>>>();
+                    snapshot.board.push(row.filter(|row| row.len() == GAME_BOARD_WIDTH && row.iter().all(|cell| *cell <= MINO_TYPES)).ok_or_else(bad_value)?);
+                },
+                "current" => {
+                    let values = parse_list::<i64>(value).ok_or_else(bad_value)?;
+                    let [mino, rotation, x, y] = values[..] else { return Err(bad_value()) };
+                    if !is_mino(mino) || !(0..Mino::new(mino as u8).rotation_count() as i64).contains(&rotation) { return Err(bad_value()); }
+                    snapshot.current_mino = mino as u8;
+                    snapshot.rotation = rotation as usize;
+                    snapshot.position = (i16::try_from(x).map_err(|_| bad_value())?, i16::try_from(y).map_err(|_| bad_value())?);
+                },
+                "next" => snapshot.next_queue = parse_list(value).filter(|queue: &Vec<u8>| queue.iter().all(|mino| is_mino(*mino as i64))).ok_or_else(bad_value)?,
+                "hold" => snapshot.hold_mino = parse_optional(value).filter(|mino: &Option<u8>| mino.is_none_or(|mino| is_mino(mino as i64))).ok_or_else(bad_value)?,
+                "hold_used" => snapshot.hold_used = value.parse().map_err(|_| bad_value())?,
+                "gravity_counter" => snapshot.gravity_counter = value.parse().map_err(|_| bad_value())?,
+                "lock_timer" => snapshot.lock_timer = parse_optional(value).ok_or_else(bad_value)?,
+                "lock_resets" => snapshot.lock_resets = value.parse().map_err(|_| bad_value())?,
+                "lowest_row" => snapshot.lowest_row = value.parse().map_err(|_| bad_value())?,
+                "last_kick" => snapshot.last_kick = parse_optional(value).ok_or_else(bad_value)?,
+                "push_down" => snapshot.push_down = value.parse().map_err(|_| bad_value())?,
+                "back_to_back" => snapshot.back_to_back = value.parse().map_err(|_| bad_value())?,
+                "combo" => snapshot.combo = parse_optional(value).ok_or_else(bad_value)?,
+                "rows_cleared" => snapshot.rows_cleared = parse_list(value).filter(|rows: &Vec<usize>| rows.iter().all(|row| *row < GAME_BOARD_HEIGHT)).ok_or_else(bad_value)?,
+                "clear_frames" => snapshot.clear_frames = value.parse().map_err(|_| bad_value())?,
+                "buffered" => snapshot.buffered_actions = value.split_whitespace().map(Action::from_name).collect::<Option<Vec<Action>>>().ok_or_else(bad_value)?,
+                frame => {
+                    let frame = frame.parse().map_err(|_| invalid(number, &format!("unknown entry: {key}")))?;
+                    let input = Input::from_text(value).ok_or_else(|| invalid(number, &format!("unknown input: {value}")))?;
+                    snapshot.inputs.push_back((frame, input));
+                }
+            }
+        }
+
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    //the pieces that have to fit together for the game to carry on at all
+    pub fn validate(&self) -> Result<(), SnapshotError> {
+        if self.board.len() != GAME_BOARD_HEIGHT || self.board.iter().any(|row| row.len() != GAME_BOARD_WIDTH) {
+            return Err(SnapshotError(format!("expected {GAME_BOARD_HEIGHT} board rows of {GAME_BOARD_WIDTH}, found {}", self.board.len())));
+        }
+        if self.statistics.len() != MINO_TYPES as usize {
+            return Err(SnapshotError(format!("expected {MINO_TYPES} statistics, found {}", self.statistics.len())));
+        }
+        if self.next_queue.len() != MAX_NEXT_COUNT || !self.next_queue.iter().all(|mino| is_mino(*mino as i64)) {
+            return Err(SnapshotError(format!("expected {MAX_NEXT_COUNT} next minos, found {}", self.next_queue.len())));
+        }
+        if !(1..=MAX_NEXT_COUNT).contains(&self.next_count) {
+            return Err(SnapshotError(format!("next_count is {}, it can be 1 to {MAX_NEXT_COUNT}", self.next_count)));
+        }
+        if self.handling.soft_drop_factor == 0 {
+            return Err(SnapshotError("sdf is 0, it needs to be at least 1".to_string()));
+        }
+        //counters the game only ever runs up to a limit - past it they'd overflow on the next frame
+        if self.gravity_counter > GRAVITY_TABLE[0] {
+            return Err(SnapshotError(format!("gravity_counter is {}, it can be 0 to {}", self.gravity_counter, GRAVITY_TABLE[0])));
+        }
+        if let Some(frames) = self.lock_timer.filter(|frames| *frames > LOCK_DELAY_FRAMES) {
+            return Err(SnapshotError(format!("lock_timer is {frames}, it can be 0 to {LOCK_DELAY_FRAMES}")));
+        }
+        if self.lock_resets > LOCK_RESET_LIMIT {
+            return Err(SnapshotError(format!("lock_resets is {}, it can be 0 to {LOCK_RESET_LIMIT}", self.lock_resets)));
+        }
+        if let Some(combo) = self.combo.filter(|combo| *combo >= self.lines.max(1)) {
+            return Err(SnapshotError(format!("combo is {combo} with only {} lines cleared", self.lines)));
+        }
+        //rows are dropped from the bottom up one at a time, so each can only be in there once
+        if !self.rows_cleared.windows(2).all(|rows| rows[0] < rows[1]) || self.rows_cleared.iter().any(|row| *row >= GAME_BOARD_HEIGHT) {
+            return Err(SnapshotError(format!("rows_cleared {} aren't rows of the board in order", list(&self.rows_cleared))));
+        }
+        let max_clear_frames = LINE_CLEAR_FRAMES + LINE_CLEAR_FRAMES_PER_ROW * 4;
+        if self.clear_frames > max_clear_frames {
+            return Err(SnapshotError(format!("clear_frames is {}, it can be 0 to {max_clear_frames}", self.clear_frames)));
+        }
+        if !self.randomizer.create(self.seed).set_state(&self.randomizer_state) {
+            return Err(SnapshotError(format!("randomizer state doesn't fit the {} randomizer", self.randomizer.name())));
+        }
+        if !is_mino(self.current_mino as i64) || self.rotation >= Mino::new(self.current_mino).rotation_count() {
+            return Err(SnapshotError(format!("current mino {} can't be turned to {}", self.current_mino, self.rotation)));
+        }
+        //while rows are clearing the mino that filled them is already part of the board, and the next one hasn't come in yet
+        let mut mino = Mino::new(self.current_mino);
+        mino.current_rotation = self.rotation;
+        if self.rows_cleared.is_empty() && (self.position.0 % 2 != 0 || !fits(&self.board, mino.get_rotation(), self.position)) {
+            return Err(SnapshotError(format!("current mino at {} {} doesn't fit on the board", self.position.0, self.position.1)));
+        }
+        Ok(())
+    }
+
+    //what's left once the file is read is whatever it didn't mention, and gets checked afterwards
+    fn blank() -> Self {
+        Self {
+            mode: GameMode::Marathon,
+            time_limit: frames_from_seconds(ULTRA_SECONDS),
+            scoring: Scoring::Classic,
+            ruleset: Ruleset::Standard,
+            start_level: 0,
+            handling: Handling::default(),
+            next_count: DEFAULT_NEXT_COUNT,
+            randomizer: DEFAULT_RANDOMIZER,
+            seed: 0,
+            randomizer_state: vec![],
+            seeds: 0,
+            fixed_seed: None,
+            frame: 0,
+            play_frames: 0,
+            score: 0,
+            level: 0,
+            lines: 0,
+            statistics: vec![],
+            splits: vec![],
+            board: vec![],
+            current_mino: T_BLOCK,
+            rotation: 0,
+            position: NO_OFFSET,
+            next_queue: vec![],
+            hold_mino: None,
+            hold_used: false,
+            gravity_counter: 0,
+            lock_timer: None,
+            lock_resets: 0,
+            lowest_row: 0,
+            last_kick: None,
+            push_down: 0,
+            back_to_back: false,
+            combo: None,
+            rows_cleared: vec![],
+            clear_frames: 0,
+            buffered_actions: vec![],
+            inputs: VecDeque::new(),
+        }
+    }
+}
+
+fn list<T: Display>(values: &[T]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn optional<T: Display>(value: Option<T>) -> String {
+    value.map_or("none".to_string(), |value| value.to_string())
+}
+
+fn parse_list<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split_whitespace().map(|value| value.parse().ok()).collect()
+}
+
+fn parse_optional<T: FromStr>(value: &str) -> Option<Option<T>> {
+    if value == "none" { return Some(None); }
+    value.parse().ok().map(Some)
+}
+
+fn is_mino(value: i64) -> bool {
+    (1..=MINO_TYPES as i64).contains(&value)
+}
+
+//what went wrong reading a snapshot
+#[derive(Debug)]
+pub struct SnapshotError(pub String);
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SnapshotError {}
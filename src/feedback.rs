@@ -0,0 +1,139 @@
+use rata_tetris::engine::GameEvent;
+use crate::consts::*;
+
+//the big moments of a game got across without a sound device, for playing over ssh - a terminal bell, a flash of the game's border, or both
+#[derive(Clone, Copy, PartialEq)]
+pub enum Cue {
+    Off,
+    Bell,
+    Flash,
+    Both,
+}
+
+impl Cue {
+    pub const ALL: [Self; 4] = [Self::Off, Self::Bell, Self::Flash, Self::Both];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Bell => "bell",
+            Self::Flash => "flash",
+            Self::Both => "both",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|cue| cue.name() == name)
+    }
+
+    fn bell(self) -> bool {
+        matches!(self, Self::Bell | Self::Both)
+    }
+
+    fn flash(self) -> bool {
+        matches!(self, Self::Flash | Self::Both)
+    }
+}
+
+//the events that light up the border, each in its own color from the theme
+#[derive(Clone, Copy, PartialEq)]
+pub enum Flash {
+    Tetris,
+    LevelUp,
+    TopOut,
+    TopScore,
+}
+
+//which cue each event gets, from the config file
+#[derive(Clone, Copy)]
+pub struct Cues {
+    pub tetris: Cue,
+    pub level_up: Cue,
+    pub top_out: Cue,
+    pub top_score: Cue,
+}
+
+//turns a frame's events into a ring of the bell and a border color for a few frames
+pub struct Feedback {
+    cues: Cues,
+    bell: bool,
+    flash: Option<(Flash, u16)>, //what it's for and frames left
+}
+
+impl Feedback {
+    pub fn new(cues: Cues) -> Self {
+        Self { cues, bell: false, flash: None }
+    }
+
+    //called every frame, so a flash fades on time even when nothing happens
+    pub fn on_events(&mut self, events: &[GameEvent]) {
+        self.flash = self.flash.and_then(|(flash, frames)| (frames > 1).then_some((flash, frames - 1)));
+        for event in events {
+            let (cue, flash) = match event {
+                GameEvent::LinesCleared { count: 4 } => (self.cues.tetris, Flash::Tetris),
+                GameEvent::LevelUp { .. } => (self.cues.level_up, Flash::LevelUp),
+                GameEvent::TopOut => (self.cues.top_out, Flash::TopOut),
+                //a new top score comes in on the same frame as the top out, and takes over from it
+                GameEvent::NewTopScore { .. } => (self.cues.top_score, Flash::TopScore),
+                _ => continue,
+            };
+            self.bell |= cue.bell();
+            if cue.flash() {
+                self.flash = Some((flash, FLASH_FRAMES));
+            }
+        }
+    }
+
+    //the bell rings once however many events asked for it
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell)
+    }
+
+    pub fn flash(&self) -> Option<Flash> {
+        self.flash.map(|(flash, _)| flash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues(cue: Cue) -> Cues {
+        Cues { tetris: cue, level_up: cue, top_out: cue, top_score: cue }
+    }
+
+    #[test]
+    fn a_flash_lasts_its_frames_then_fades() {
+        let mut feedback = Feedback::new(cues(Cue::Flash));
+        feedback.on_events(&[GameEvent::LinesCleared { count: 4 }]);
+        assert!(feedback.flash() == Some(Flash::Tetris));
+        assert!(!feedback.take_bell());
+        (1..FLASH_FRAMES).for_each(|_| feedback.on_events(&[]));
+        assert!(feedback.flash() == Some(Flash::Tetris));
+        feedback.on_events(&[]);
+        assert!(feedback.flash().is_none());
+
+        //fewer than four lines doesn't count
+        feedback.on_events(&[GameEvent::LinesCleared { count: 3 }]);
+        assert!(feedback.flash().is_none());
+    }
+
+    #[test]
+    fn a_new_top_score_takes_over_from_the_top_out() {
+        let mut feedback = Feedback::new(cues(Cue::Both));
+        feedback.on_events(&[GameEvent::TopOut, GameEvent::NewTopScore { score: 1000 }]);
+        assert!(feedback.flash() == Some(Flash::TopScore));
+        //the bell only rings once for the both of them
+        assert!(feedback.take_bell());
+        assert!(!feedback.take_bell());
+    }
+
+    #[test]
+    fn cues_left_off_do_nothing() {
+        let mut feedback = Feedback::new(Cues { level_up: Cue::Bell, ..cues(Cue::Off) });
+        feedback.on_events(&[GameEvent::LinesCleared { count: 4 }, GameEvent::TopOut]);
+        assert!(feedback.flash().is_none() && !feedback.take_bell());
+        feedback.on_events(&[GameEvent::LevelUp { level: 3 }]);
+        assert!(feedback.flash().is_none() && feedback.take_bell());
+    }
+}
@@ -0,0 +1,186 @@
+use ratatui::crossterm::event::KeyCode;
+
+use rata_tetris::engine::Action;
+use crate::consts::*;
+
+//which keys trigger which actions while playing, any number of keys per action
+pub struct KeyBindings {
+    bindings: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let bindings = DEFAULT_KEYS.iter().map(|(action, keys)| {
+            //the defaults are known good names
+            (*action, keys.iter().filter_map(|key| parse_key(key)).collect())
+        }).collect();
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings.iter().find(|(_, keys)| keys.contains(&code)).map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.iter().find(|(other, _)| *other == action).map_or(&[], |(_, keys)| keys)
+    }
+
+    //the footer while playing, showing the first key on each action
+    pub fn controls_text(&self) -> String {
+        let first = |action| self.keys(action).first().map_or("-".to_string(), |code| key_label(*code));
+        format!(
+            " start: space quit: q rot: {}/{} move: {}{} slam: {} drop: {} hold: {}",
+            first(Action::RotateLeft),
+            first(Action::RotateRight),
+            first(Action::MoveLeft),
+            first(Action::MoveRight),
+            first(Action::HardDrop),
+            first(Action::SoftDrop),
+            first(Action::Hold),
+        )
+    }
+
+    //swap out the keys for one action - checking for clashes is up to the caller
+    pub fn bind(&mut self, action: Action, keys: Vec<KeyCode>) {
+        match self.bindings.iter_mut().find(|(other, _)| *other == action) {
+            Some((_, bound)) => *bound = keys,
+            None => self.bindings.push((action, keys)),
+        }
+    }
+
+    //put a key in one of an action's slots, leaving its other keys alone - a slot past the end adds the key after them
+    pub fn set_key(&mut self, action: Action, slot: usize, code: KeyCode) {
+        match self.bindings.iter_mut().find(|(other, _)| *other == action) {
+            Some((_, bound)) if slot < bound.len() => bound[slot] = code,
+            Some((_, bound)) => bound.push(code),
+            None => self.bindings.push((action, vec![code])),
+        }
+    }
+
+    //take the key out of one of an action's slots, handing it back if there was one
+    pub fn remove_key(&mut self, action: Action, slot: usize) -> Option<KeyCode> {
+        let (_, bound) = self.bindings.iter_mut().find(|(other, _)| *other == action)?;
+        (slot < bound.len()).then(|| bound.remove(slot))
+    }
+
+    //the first action found that shares a key with another, for error messages
+    pub fn conflict(&self) -> Option<(KeyCode, Action, Action)> {
+        self.bindings.iter().enumerate().find_map(|(index, (action, keys))| {
+            self.bindings[index + 1..].iter().find_map(|(other, other_keys)| {
+                keys.iter().find(|key| other_keys.contains(key)).map(|key| (*key, *action, *other))
+            })
+        })
+    }
+}
+
+//key names as they're written in the config file - single characters stand for themselves
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let code = match name.to_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        "backspace" => KeyCode::Backspace,
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "esc" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        function if function.len() > 1 && function.starts_with('f') => KeyCode::F(function[1..].parse().ok().filter(|number| (1..=12).contains(number))?),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+                _ => return None,
+            }
+        },
+    };
+    Some(code)
+}
+
+//whether a key comes back as itself once it's saved to the config file and read in again - rebinding turns away any that wouldn't
+pub fn can_save(code: KeyCode) -> bool {
+    parse_key(&key_name(code)) == Some(code)
+}
+
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::F(number) => format!("f{number}"),
+        KeyCode::Char(c) => c.to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+//shorter names for on screen, arrows get drawn as arrows
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::PageUp => "pgup".to_string(),
+        KeyCode::PageDown => "pgdn".to_string(),
+        code => key_name(code),
+    }
+}
+
+//space pauses and q quits while playing, so neither can be handed to an action
+pub fn is_reserved(code: KeyCode) -> bool {
+    RESERVED_KEYS.iter().any(|key| parse_key(key) == Some(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_key_name_reads_back_as_the_same_key() {
+        let named = [
+            KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down, KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home,
+            KeyCode::End, KeyCode::Insert, KeyCode::Delete, KeyCode::Backspace, KeyCode::Enter, KeyCode::Tab, KeyCode::Esc,
+        ];
+        let characters = (' '..='~').filter(|c| !c.is_ascii_uppercase()).map(KeyCode::Char);
+        let functions = (1..=12).map(KeyCode::F);
+        for code in named.into_iter().chain(characters).chain(functions) {
+            assert!(can_save(code), "{} doesn't read back", key_name(code));
+        }
+        //keys with no name, function keys the file can't hold, and shifted letters that would read back lower case
+        for code in [KeyCode::BackTab, KeyCode::Null, KeyCode::CapsLock, KeyCode::F(13), KeyCode::Char('A')] {
+            assert!(!can_save(code), "{} reads back", key_name(code));
+        }
+    }
+
+    #[test]
+    fn rebinding_a_slot_keeps_the_other_keys() {
+        let mut keys = KeyBindings::default();
+        keys.bind(Action::Hold, vec![KeyCode::Char('c'), KeyCode::Tab]);
+        keys.set_key(Action::Hold, 1, KeyCode::Char('v'));
+        keys.set_key(Action::Hold, 5, KeyCode::Char('b'));
+        assert_eq!(keys.keys(Action::Hold), [KeyCode::Char('c'), KeyCode::Char('v'), KeyCode::Char('b')]);
+        assert_eq!(keys.remove_key(Action::Hold, 0), Some(KeyCode::Char('c')));
+        assert_eq!(keys.remove_key(Action::Hold, 2), None);
+        assert_eq!(keys.keys(Action::Hold), [KeyCode::Char('v'), KeyCode::Char('b')]);
+    }
+}
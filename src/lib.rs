@@ -0,0 +1,2 @@
+//the rules of the game with no terminal, threads or files attached - the rata-tetris binary is one front end for it
+pub mod engine;
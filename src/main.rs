@@ -1,79 +1,465 @@
 mod ui;
-mod game;
-mod minos;
 mod consts;
-//mod audio;
+mod storage;
+mod scores;
+mod config;
+mod keys;
+mod audio;
+mod music;
+mod feedback;
+mod theme;
+mod colors;
 
 use std::{
-    io::{self}, 
-    thread
+    env,
+    io::{self, Write}, 
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::{mpsc::{self, Receiver}, Mutex},
+    time::{Duration, Instant},
 };
 
-use consts::{STATE_GAME_OVER, STATE_PAUSED, STATE_PLAYING, STATE_START_SCREEN};
+use audio::{Audio, SoundPack};
+use colors::ColorDepth;
+use consts::{BELL, DEFAULT_HIGH_SCORE_NAME, FRAME_RATE_NTSC, HIGH_SCORE_NAME_LENGTH, MAX_CATCH_UP_FRAMES, MAX_START_LEVEL, MUSIC_OFF, MUSIC_SAMPLE_RATE, SOUND_STARTUP, TUNES};
+use rata_tetris::engine::game::frames_from_seconds;
+use rata_tetris::engine::{Action, Game, GameEvent, GameMode, GameState, Input, Overrides, RandomizerKind, Replay, Ruleset, Scoring, Snapshot};
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
+        execute,
+        terminal::supports_keyboard_enhancement,
+    },
     DefaultTerminal,
 };
 
-use ui::draw_ui;
-
-use game::Game;
+use config::Config;
+use feedback::Feedback;
+use keys::{can_save, is_reserved, key_label, KeyBindings};
+use music::{MusicControl, Synth, Tune};
+use scores::{HighScore, HighScores};
+use ui::{draw_ui, DisplaySettings, Screen};
 
 fn main() -> io::Result<()> {
+    //a config file with mistakes in it stops the game before it starts, saying what's wrong and where
+    let mut config = storage::load_config().unwrap_or_else(|e| {
+        eprintln!("couldn't load config file {e}");
+        process::exit(1);
+    });
+    //--theme <name> picks the colors over the config file's - an unknown name or a broken theme file stops the game like the config does
+    let theme_name = arg_value::<String>("--theme").unwrap_or(config.theme_name.clone());
+    config.theme = storage::load_theme(&theme_name).unwrap_or_else(|e| {
+        eprintln!("couldn't load theme: {e}");
+        process::exit(1);
+    });
+    //--music <name|off> picks the background music over the config file's
+    let tune = match arg_value::<String>("--music") {
+        Some(name) if name == MUSIC_OFF => None,
+        Some(name) => music::find_tune(&name).or(config.music),
+        None => config.music,
+    };
+    //--render-music <file> writes one time through the music to a wav instead of playing, at the tempo for --level and --stack
+    if let Some(file_path) = arg_value::<PathBuf>("--render-music") {
+        return render_music(&file_path, tune.unwrap_or(&TUNES[0]));
+    }
+    //--replay <file> watches a recorded game instead of playing one - load it up front so a bad file errors before the screen is taken over
+    let replay = match arg_value::<PathBuf>("--replay") {
+        Some(file_path) => Some(storage::load_replay(&file_path).map_err(|e| {
+            io::Error::new(e.kind(), format!("couldn't load replay {}: {e}", file_path.display()))
+        })?),
+        None => None,
+    };
+    //a broken high score file shouldn't stop anyone playing, it just starts the tables over
+    let high_scores = storage::load_high_scores().unwrap_or_else(|e| {
+        eprintln!("couldn't load high scores file: {e}");
+        HighScores::default()
+    });
+    //same for a suspended game that can't be read back, it's dropped and the game starts fresh
+    let suspended = storage::load_suspended_game().unwrap_or_else(|e| {
+        eprintln!("couldn't load suspended game file: {e}");
+        None
+    });
+    //--sound-pack <name> picks a sound pack over the config file's, and one that can't be loaded leaves the built in sounds
+    let sound_pack = match arg_value::<String>("--sound-pack").or(config.sound_pack.clone()) {
+        Some(name) => storage::load_sound_pack(&name).unwrap_or_else(|e| {
+            eprintln!("couldn't load sound pack {name}: {e}");
+            SoundPack::default()
+        }),
+        None => SoundPack::default(),
+    };
+    //the sound device is opened before the screen is taken over, since audio libraries like to complain on stderr
+    let mut audio = Audio::open(tune, sound_pack);
+    audio.play(SOUND_STARTUP);
+
     let mut terminal = ratatui::init();
     terminal.clear()?;
-    let app_result = run(terminal);
+
+    //ask the terminal for press, repeat and release events so held keys can be tracked - not every terminal supports this
+    let key_releases = supports_keyboard_enhancement().unwrap_or(false);
+    if key_releases {
+        execute!(io::stdout(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+    }
+
+    let app_result = run(terminal, key_releases, replay, high_scores, suspended, config, &mut audio);
+
+    if key_releases {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+    }
     ratatui::restore();
+    //now there's a terminal to print to again
+    DEFERRED_ERRORS.lock().unwrap().drain(..).for_each(|message| eprintln!("{message}"));
     app_result
 }
 
-fn run(terminal: DefaultTerminal) -> io::Result<()> {
+//anything that goes wrong while the screen is taken over waits here until it's given back, since printing would draw over the game
+static DEFERRED_ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-    let (stop_sender, stop_receiver) = std::sync::mpsc::channel();
+fn report(message: String) {
+    DEFERRED_ERRORS.lock().unwrap().push(message);
+}
 
-    let game = Game::new();
-    let game_clone = game.clone();
+//the music as it would sound at a given level and stack height, for listening to without playing
+fn render_music(file_path: &Path, tune: &Tune) -> io::Result<()> {
+    let control = MusicControl::new();
+    control.set_playing(true);
+    control.set_tempo(music::tempo(arg_value("--level").unwrap_or(0), arg_value("--stack").unwrap_or(0)));
+    let mut synth = Synth::new(tune, control);
+    let samples = synth.render(synth.loop_samples());
+    storage::save_wav(file_path, &samples).map_err(|e| {
+        io::Error::new(e.kind(), format!("couldn't write {}: {e}", file_path.display()))
+    })?;
+    println!("wrote {} to {} - {:.1} seconds", tune.name, file_path.display(), samples.len() as f32 / MUSIC_SAMPLE_RATE as f32);
+    Ok(())
+}
 
-    let draw_thread_handle = thread::spawn(|| -> io::Result<()> {
-        println!("hello from draw thread...");
-        draw_ui(terminal, game_clone, stop_receiver)?;
-        Ok(())
-    });
+//value following a command line flag, e.g. --next 5
+fn arg_value<T: FromStr>(flag: &str) -> Option<T> {
+    env::args()
+        .skip_while(|arg| arg != flag)
+        .nth(1)
+        .and_then(|value| value.parse().ok())
+}
+
+fn run(mut terminal: DefaultTerminal, key_releases: bool, replay: Option<Replay>, mut high_scores: HighScores, suspended: Option<Snapshot>, config: Config, audio: &mut Audio) -> io::Result<()> {
+    let mut keys = config.keys;
+    //optional --colors <truecolor|256|16|mono> flag says what the terminal can show, instead of working it out
+    let colors = arg_value::<String>("--colors").and_then(|name| ColorDepth::from_name(&name)).or(config.colors).unwrap_or_else(ColorDepth::detect);
+    let display = DisplaySettings::new(config.ghost, config.theme, colors);
+    let mut feedback = Feedback::new(config.cues);
+
+    let mut game = Game::new(rand::random());
+    sync_high_scores(&mut game, &high_scores);
+    let mut screen = Screen::Game;
+
+    //the config file sets things up first, then any command line flags override it
+    game.mode = config.mode;
+    game.scoring = config.scoring;
+    game.set_start_level(config.start_level);
+    let (event_sender, events) = mpsc::channel();
+    game.subscribe(move |event| { let _ = event_sender.send(event); });
+
+    //optional --level <0-19> flag sets the starting level
+    if let Some(level) = arg_value("--level") {
+        game.set_start_level(level);
+    }
+    //the ruleset brings its own piece generator, handling and preview - whatever the config or the optional --das, --arr (in frames), --sdf,
+    //--next <1-6> and --randomizer <bag|nes|tgm|random> flags pick is kept as an override, so it survives switching rules on the start screen
+    game.overrides = Overrides {
+        das: arg_value("--das").or(config.das),
+        arr: arg_value("--arr").or(config.arr),
+        soft_drop_factor: arg_value("--sdf").or(config.soft_drop_factor),
+        next_count: arg_value("--next").or(config.next_count),
+        randomizer: arg_value::<String>("--randomizer").and_then(|name| RandomizerKind::from_name(&name)),
+    };
+    //optional --seed flag deals the same sequence every game
+    game.fixed_seed = arg_value("--seed");
+    //optional --rules <standard|nes> flag picks the ruleset
+    game.set_ruleset(arg_value::<String>("--rules").and_then(|name| Ruleset::from_name(&name)).unwrap_or(config.ruleset));
+    //optional --mode <marathon|sprint|ultra> flag picks the mode the start screen opens on, --time-limit sets ultra's clock in seconds
+    if let Some(mode) = arg_value::<String>("--mode").and_then(|name| GameMode::from_name(&name)) {
+        game.mode = mode;
+    }
+    if let Some(seconds) = arg_value::<f64>("--time-limit").filter(|seconds| *seconds > 0.0) {
+        game.time_limit = frames_from_seconds(seconds);
+    }
+    //optional --scoring <classic|guideline> flag picks how points are handed out
+    if let Some(scoring) = arg_value::<String>("--scoring").and_then(|name| Scoring::from_name(&name)) {
+        game.scoring = scoring;
+    }
+    //a game quit part way through last time is offered before anything else, unless there's a replay to watch
+    match (replay, suspended) {
+        (Some(replay), _) => game.start_playback(replay),
+        (None, Some(snapshot)) => screen = Screen::Continue(Box::new(snapshot)),
+        (None, None) => {},
+    }
+
+    //one thread, one loop - input is collected as it arrives, and the game steps exactly one frame per tick
+    let frame_duration = Duration::from_secs_f64(1.0 / FRAME_RATE_NTSC);
+    let mut next_frame = Instant::now();
+    let mut inputs = vec![];
 
     loop {
-        if let Event::Key(key) = event::read()? {
-            match key.kind {
-                KeyEventKind::Press => {
-                    let mut game = game.lock().unwrap();
-                    match key.code {
-                        KeyCode::Up =>          game.slam(),
-                        KeyCode::Down =>        game.move_down(),
-                        KeyCode::Left =>        game.move_left(),
-                        KeyCode::Right =>       game.move_right(),
-                        KeyCode::PageUp =>      game.rotate_left(),
-                        KeyCode::PageDown =>    game.rotate_right(),
-                        KeyCode::Char(' ') => {
-                            //key has multiple uses
-                            match game.game_state {
-                                STATE_START_SCREEN => game.start_game(),
-                                STATE_PAUSED => game.toggle_paused(),
-                                STATE_PLAYING => game.toggle_paused(),
-                                STATE_GAME_OVER => game.new_game(),
-                                _ => {}
-                            }
-                        }
-                        KeyCode::Char('q') => {
-                            break;
-                        },
-                        _ => {}
-                    }
+        if event::poll(next_frame.saturating_duration_since(Instant::now()))? {
+            if let Event::Key(key) = event::read()? {
+                if handle_key(&mut game, &mut screen, &mut high_scores, &mut keys, &mut inputs, key, key_releases) {
+                    break;
                 }
-                _ => {},
             }
         }
+        if Instant::now() < next_frame { continue; }
+
+        //catch up on any frames missed while busy, but don't fast forward through a long stall
+        let mut frames = 0;
+        while Instant::now() >= next_frame && frames < MAX_CATCH_UP_FRAMES {
+            step(&mut game, &mut screen, &high_scores, &std::mem::take(&mut inputs), &events, audio, &mut feedback);
+            next_frame += frame_duration;
+            frames += 1;
+        }
+        if Instant::now() >= next_frame {
+            next_frame = Instant::now() + frame_duration;
+        }
+
+        //the bell goes straight to the terminal, which is all there is over ssh
+        if feedback.take_bell() {
+            terminal.backend_mut().write_all(BELL)?;
+            terminal.backend_mut().flush()?;
+        }
+        terminal.draw(|frame| draw_ui(frame, &game, &screen, &high_scores, &keys, &display, feedback.flash()))?;
+    }
+
+    //quitting part way through a game keeps it for next time
+    if let Some(snapshot) = game.suspend() {
+        if let Err(e) = storage::save_suspended_game(&snapshot) {
+            report(format!("couldn't save suspended game file: {e}"));
+        }
     }
 
-    stop_sender.send(()).unwrap();
-    draw_thread_handle.join().unwrap()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+//the engine leaves keeping the scores and the recording up to us once a game ends, and sounds and flashes play off the frame's events
+fn step(game: &mut Game, screen: &mut Screen, high_scores: &HighScores, inputs: &[Input], events: &Receiver<GameEvent>, audio: &mut Audio, feedback: &mut Feedback) {
+    game.step(inputs);
+    let events = events.try_iter().collect::<Vec<GameEvent>>();
+    audio.play_events(&events);
+    audio.update(game);
+    feedback.on_events(&events);
+    for event in events {
+        match event {
+            GameEvent::TopOut | GameEvent::Finished { .. } | GameEvent::TimeUp { .. } => {
+                if let Some(replay) = game.take_replay() {
+                    if let Err(e) = storage::save_replay(&replay) {
+                        report(format!("couldn't save replay file: {e}"));
+                    }
+                }
+                //a game good enough for its table asks for a name before anything else
+                if let Some(rank) = high_scores.rank(game) {
+                    *screen = Screen::NameEntry { entry: HighScore::from_game(game), rank };
+                }
+            },
+            GameEvent::NewPersonalBest { .. } => if let Err(e) = storage::save_sprint_best(&game.splits) {
+                report(format!("couldn't save sprint best file: {e}"));
+            },
+            _ => {}
+        }
+    }
+}
+
+//the tables and the sprint best file are the only records kept - the engine is handed the score and splits to beat before every game
+fn sync_high_scores(game: &mut Game, high_scores: &HighScores) {
+    game.top_score = high_scores.table(GameMode::Marathon).first().map_or(0, |entry| entry.score);
+    game.best_splits = storage::load_sprint_best().unwrap_or_default();
+}
+
+//returns true once the player asks to quit - game inputs are queued up for the next frame
+fn handle_key(game: &mut Game, screen: &mut Screen, high_scores: &mut HighScores, keys: &mut KeyBindings, inputs: &mut Vec<Input>, key: KeyEvent, key_releases: bool) -> bool {
+    //the name entry and high score screens take the keyboard over from the game
+    match screen {
+        Screen::NameEntry { .. } => {
+            if key.kind == KeyEventKind::Press {
+                handle_name_key(game, screen, high_scores, key.code);
+            }
+            return false;
+        },
+        Screen::HighScores(mode) => {
+            if key.kind == KeyEventKind::Press {
+                match key.code {
+                    KeyCode::Char(' ') | KeyCode::Esc | KeyCode::Char('h') => *screen = Screen::Game,
+                    KeyCode::Left => *mode = mode.previous(),
+                    KeyCode::Right => *mode = mode.next(),
+                    KeyCode::Char('q') => return true,
+                    _ => {}
+                }
+            }
+            return false;
+        },
+        Screen::Controls { .. } => {
+            if key.kind == KeyEventKind::Press {
+                handle_controls_key(screen, keys, key.code);
+            }
+            return false;
+        },
+        Screen::Continue(_) => {
+            if key.kind == KeyEventKind::Press {
+                return handle_continue_key(game, screen, key.code);
+            }
+            return false;
+        },
+        Screen::Game => {},
+    }
+    //while a replay is playing the keyboard only gets to quit
+    let watching = game.playback.is_some() && !game.is_over();
+    if game.game_state == GameState::StartScreen && key.kind == KeyEventKind::Press && handle_start_screen_key(game, screen, key.code) {
+        return false;
+    }
+    if let Some(action) = keys.action(key.code).filter(|_| !watching) {
+        match key.kind {
+            KeyEventKind::Press if key_releases => inputs.push(Input::Press(action)),
+            KeyEventKind::Release => inputs.push(Input::Release(action)),
+            //without release events every press - including the terminal's own key repeat - is a single tap
+            KeyEventKind::Press => inputs.extend([Input::Press(action), Input::Release(action)]),
+            //repeats are ignored since das and arr handle auto repeat inside the game
+            KeyEventKind::Repeat => {},
+        }
+    } else if key.kind == KeyEventKind::Press {
+        match key.code {
+            KeyCode::Char(' ') if !watching => {
+                //key has multiple uses
+                match game.game_state {
+                    GameState::StartScreen => {
+                        sync_high_scores(game, high_scores);
+                        game.start_game();
+                    },
+                    GameState::Paused | GameState::Playing => inputs.push(Input::Pause),
+                    GameState::GameOver | GameState::Finished => {
+                        sync_high_scores(game, high_scores);
+                        game.new_game();
+                    },
+                }
+            }
+            KeyCode::Char('q') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+//the start screen picks the mode, the rules and the starting level, and opens the high scores and controls - returns true if the key was used
+fn handle_start_screen_key(game: &mut Game, screen: &mut Screen, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Char('h') => *screen = Screen::HighScores(game.mode),
+        KeyCode::Char('k') => *screen = Screen::Controls { selected: 0, slot: 0, capturing: false, message: String::new() },
+        KeyCode::Char('m') => game.mode = game.mode.next(),
+        KeyCode::Char('r') => game.set_ruleset(game.ruleset.next()),
+        KeyCode::Left => game.set_start_level(game.start_level.checked_sub(1).unwrap_or(MAX_START_LEVEL)),
+        KeyCode::Right => game.set_start_level((game.start_level + 1) % (MAX_START_LEVEL + 1)),
+        _ => return false,
+    }
+    true
+}
+
+//carry on with the suspended game or pass it over for a new one - either way it's gone from disk, returns true to quit
+fn handle_continue_key(game: &mut Game, screen: &mut Screen, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Enter | KeyCode::Esc => {},
+        KeyCode::Char('q') => return true,
+        _ => return false,
+    }
+    let Screen::Continue(snapshot) = std::mem::replace(screen, Screen::Game) else { return false };
+    if code == KeyCode::Enter {
+        if let Err(e) = game.resume(*snapshot) {
+            report(format!("couldn't continue the suspended game: {e}"));
+        }
+    }
+    if let Err(e) = storage::remove_suspended_game() {
+        report(format!("couldn't remove suspended game file: {e}"));
+    }
+    false
+}
+
+//typing a name for the high score table - enter saves it and shows the table it went on
+fn handle_name_key(game: &Game, screen: &mut Screen, high_scores: &mut HighScores, code: KeyCode) {
+    let Screen::NameEntry { entry, .. } = screen else { return };
+    match code {
+        KeyCode::Char(c) if c.is_ascii_alphanumeric() && entry.name.len() < HIGH_SCORE_NAME_LENGTH => entry.name.push(c.to_ascii_uppercase()),
+        KeyCode::Backspace => { entry.name.pop(); },
+        KeyCode::Enter => {
+            let mut entry = entry.clone();
+            if entry.name.is_empty() {
+                entry.name = DEFAULT_HIGH_SCORE_NAME.to_string();
+            }
+            high_scores.insert(game.mode, entry);
+            if let Err(e) = storage::save_high_scores(high_scores) {
+                report(format!("couldn't save high scores file: {e}"));
+            }
+            *screen = Screen::HighScores(game.mode);
+        },
+        _ => {}
+    }
+}
+
+//the controls screen - pick an action and one of its keys, or the empty slot after them, press enter, then the key to put there - every change is saved straight away
+fn handle_controls_key(screen: &mut Screen, keys: &mut KeyBindings, code: KeyCode) {
+    let Screen::Controls { selected, slot, capturing, message } = screen else { return };
+    let action = Action::ALL[*selected];
+    let action_name = action.name().replace('_', " ");
+    if *capturing {
+        *capturing = false;
+        *message = match code {
+            KeyCode::Esc => String::new(),
+            code if is_reserved(code) => format!("{} is taken by pause and quit", key_label(code)),
+            //the config file couldn't be read back with a key it has no name for
+            code if !can_save(code) => "can't bind that key".to_string(),
+            code => match keys.action(code) {
+                Some(other) if other == action => format!("{} is already on {action_name}", key_label(code)),
+                Some(other) => format!("{} is already on {} - rebind that first", key_label(code), other.name().replace('_', " ")),
+                None => {
+                    //only the picked key is swapped out, so say which one went
+                    let replaced = keys.keys(action).get(*slot).map(|old| format!(" instead of {}", key_label(*old))).unwrap_or_default();
+                    keys.set_key(action, *slot, code);
+                    save_key_bindings(keys, &format!("{action_name} is now on {}{replaced}", key_label(code)))
+                },
+            },
+        };
+        return;
+    }
+    match code {
+        KeyCode::Up => {
+            *selected = (*selected + Action::ALL.len() - 1) % Action::ALL.len();
+            *slot = 0;
+        },
+        KeyCode::Down => {
+            *selected = (*selected + 1) % Action::ALL.len();
+            *slot = 0;
+        },
+        //the slot after the last key is for adding another
+        KeyCode::Left => *slot = slot.saturating_sub(1),
+        KeyCode::Right => *slot = (*slot + 1).min(keys.keys(action).len()),
+        KeyCode::Delete | KeyCode::Backspace => {
+            *message = match keys.remove_key(action, *slot) {
+                Some(old) => save_key_bindings(keys, &format!("{} is off {action_name}", key_label(old))),
+                None => String::new(),
+            };
+        },
+        KeyCode::Enter => {
+            *capturing = true;
+            message.clear();
+        },
+        KeyCode::Char('d') => {
+            *keys = KeyBindings::default();
+            *slot = 0;
+            *message = save_key_bindings(keys, "controls are back to the defaults");
+        },
+        KeyCode::Esc => *screen = Screen::Game,
+        _ => {}
+    }
+}
+
+//saves the bindings to the config file, and says how it went
+fn save_key_bindings(keys: &KeyBindings, done: &str) -> String {
+    match storage::save_key_bindings(keys) {
+        Ok(()) => done.to_string(),
+        Err(e) => format!("couldn't save config file: {e}"),
+    }
+}
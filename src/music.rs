@@ -0,0 +1,328 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::consts::*;
+
+//a tune as note data, one line per voice - the melody goes to a pulse wave, the bass to a triangle, and the drums to noise
+//notes are written name:length with the length in sixteenths, e.g. c#5:4, with r for a rest and k, s and h for kick, snare and hat
+//[ ... ]n repeats whatever is inside n times
+pub struct Tune {
+    pub name: &'static str,
+    pub bpm: f32, //quarter notes per minute at normal speed
+    pub melody: &'static str,
+    pub bass: &'static str,
+    pub drums: &'static str,
+}
+
+pub fn find_tune(name: &str) -> Option<&'static Tune> {
+    TUNES.iter().find(|tune| tune.name == name)
+}
+
+//the music speeds up a little every level, and a lot more once the stack gets near the top
+pub fn tempo(level: u8, stack_height: usize) -> f32 {
+    let level_speed = 1.0 + MUSIC_LEVEL_SPEEDUP * level.min(MUSIC_MAX_SPEEDUP_LEVEL) as f32;
+    let danger_speed = if stack_height >= MUSIC_DANGER_HEIGHT { MUSIC_DANGER_SPEEDUP } else { 1.0 };
+    level_speed * danger_speed
+}
+
+//how many rows from the floor up to the highest filled cell
+pub fn stack_height(board: &[Vec<u8>]) -> usize {
+    board.iter().position(|row| row.iter().any(|cell| *cell != 0)).map_or(0, |top| board.len() - top)
+}
+
+//the game loop steers the music through this while the synth plays wherever it ended up, usually on the audio thread
+#[derive(Clone)]
+pub struct MusicControl(Arc<MusicState>);
+
+struct MusicState {
+    tempo: AtomicU32, //f32 bits
+    playing: AtomicBool,
+    restart: AtomicBool,
+}
+
+impl MusicControl {
+    pub fn new() -> Self {
+        Self(Arc::new(MusicState {
+            tempo: AtomicU32::new(1.0f32.to_bits()),
+            playing: AtomicBool::new(false),
+            restart: AtomicBool::new(false),
+        }))
+    }
+
+    pub fn set_tempo(&self, tempo: f32) {
+        self.0.tempo.store(tempo.to_bits(), Ordering::Relaxed);
+    }
+
+    fn tempo(&self) -> f32 {
+        f32::from_bits(self.0.tempo.load(Ordering::Relaxed))
+    }
+
+    //stopped music stays where it was, and picks up from there when it plays again
+    pub fn set_playing(&self, playing: bool) {
+        self.0.playing.store(playing, Ordering::Relaxed);
+    }
+
+    fn playing(&self) -> bool {
+        self.0.playing.load(Ordering::Relaxed)
+    }
+
+    //back to the top of the tune, for a new game
+    pub fn restart(&self) {
+        self.0.restart.store(true, Ordering::Relaxed);
+    }
+
+    fn take_restart(&self) -> bool {
+        self.0.restart.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Note {
+    frequency: f32, //0 for a rest, or how fast the noise is clocked for drums
+    length: u32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Wave {
+    Pulse,
+    Triangle,
+    Noise,
+}
+
+//one voice loops its own notes, following the synth's position through the tune
+struct Voice {
+    wave: Wave,
+    volume: f32,
+    notes: Vec<Note>,
+    length: u32,
+    index: usize,
+    note_start: u32,
+    phase: f32,
+    noise: u16,
+}
+
+impl Voice {
+    fn new(wave: Wave, volume: f32, text: &str) -> Self {
+        let notes = parse_notes(text, wave == Wave::Noise);
+        let length = notes.iter().map(|note| note.length).sum();
+        Self { wave, volume, notes, length, index: 0, note_start: 0, phase: 0.0, noise: 1 }
+    }
+
+    fn sample(&mut self, position: f64) -> f32 {
+        if self.length == 0 { return 0.0; }
+        let local = position % self.length as f64;
+        //looping back round starts the notes over
+        if local < self.note_start as f64 {
+            self.index = 0;
+            self.note_start = 0;
+        }
+        while local >= (self.note_start + self.notes[self.index].length) as f64 {
+            self.note_start += self.notes[self.index].length;
+            self.index += 1;
+        }
+        let note = self.notes[self.index];
+        let into_note = (local - self.note_start as f64) as f32;
+        let progress = into_note / note.length as f32;
+        if note.frequency == 0.0 || progress > MUSIC_GATE { return 0.0; }
+
+        self.phase += note.frequency / MUSIC_SAMPLE_RATE as f32;
+        let wrapped = self.phase >= 1.0;
+        self.phase %= 1.0;
+        let (value, envelope) = match self.wave {
+            Wave::Pulse => (if self.phase < MUSIC_PULSE_DUTY { 1.0 } else { -1.0 }, 1.0 - 0.5 * progress),
+            Wave::Triangle => (4.0 * (self.phase - 0.5).abs() - 1.0, 1.0),
+            Wave::Noise => {
+                //15 bit lfsr like the nes noise channel, and drums only ring for a sixteenth whatever their length
+                if wrapped {
+                    let bit = (self.noise ^ (self.noise >> 1)) & 1;
+                    self.noise = (self.noise >> 1) | (bit << 14);
+                }
+                (if self.noise & 1 == 1 { 1.0 } else { -1.0 }, (1.0 - into_note).max(0.0))
+            },
+        };
+        value * envelope * self.volume
+    }
+
+    fn reset(&mut self) {
+        self.index = 0;
+        self.note_start = 0;
+        self.phase = 0.0;
+    }
+}
+
+//a tiny three voice chiptune synth - it makes samples one at a time, so it can play live or render to a buffer
+pub struct Synth {
+    voices: [Voice; 3],
+    bpm: f32,
+    position: f64, //in sixteenths
+    control: MusicControl,
+}
+
+impl Synth {
+    pub fn new(tune: &Tune, control: MusicControl) -> Self {
+        Self {
+            voices: [
+                Voice::new(Wave::Pulse, MUSIC_MELODY_VOLUME, tune.melody),
+                Voice::new(Wave::Triangle, MUSIC_BASS_VOLUME, tune.bass),
+                Voice::new(Wave::Noise, MUSIC_DRUM_VOLUME, tune.drums),
+            ],
+            bpm: tune.bpm,
+            position: 0.0,
+            control,
+        }
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if self.control.take_restart() {
+            self.position = 0.0;
+            self.voices.iter_mut().for_each(Voice::reset);
+        }
+        if !self.control.playing() { return 0.0; }
+        let position = self.position;
+        let sample = self.voices.iter_mut().map(|voice| voice.sample(position)).sum();
+        self.position += (self.bpm * self.control.tempo() * 4.0 / 60.0 / MUSIC_SAMPLE_RATE as f32) as f64;
+        sample
+    }
+
+    pub fn render(&mut self, count: usize) -> Vec<f32> {
+        (0..count).map(|_| self.next_sample()).collect()
+    }
+
+    //samples in one time through the tune at the current tempo
+    pub fn loop_samples(&self) -> usize {
+        let sixteenths = self.voices.iter().map(|voice| voice.length).max().unwrap_or_default();
+        (sixteenths as f32 * MUSIC_SAMPLE_RATE as f32 * 60.0 / (self.bpm * self.control.tempo() * 4.0)) as usize
+    }
+}
+
+impl Iterator for Synth {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_sample())
+    }
+}
+
+//the built in tunes are all known good, anything the parser doesn't know is a rest
+fn parse_notes(text: &str, drums: bool) -> Vec<Note> {
+    let mut groups: Vec<Vec<Note>> = vec![vec![]];
+    for token in text.split_whitespace() {
+        if token == "[" {
+            groups.push(vec![]);
+        } else if let Some(count) = token.strip_prefix(']') {
+            let group = groups.pop().unwrap_or_default();
+            let outer = groups.last_mut().expect("unmatched ] in tune");
+            (0..count.parse().unwrap_or(1)).for_each(|_| outer.extend(&group));
+        } else if let Some((name, length)) = token.split_once(':') {
+            let frequency = if drums { drum_clock(name) } else { pitch(name) };
+            groups.last_mut().expect("unmatched ] in tune").push(Note { frequency, length: length.parse().unwrap_or(1) });
+        }
+    }
+    groups.into_iter().flatten().collect()
+}
+
+//note names like a4 or g#3 to hertz, with a4 at 440
+fn pitch(name: &str) -> f32 {
+    let (note, octave) = name.split_at(name.len().saturating_sub(1));
+    let semitone = match note {
+        "c" => 0,
+        "c#" => 1,
+        "d" => 2,
+        "d#" => 3,
+        "e" => 4,
+        "f" => 5,
+        "f#" => 6,
+        "g" => 7,
+        "g#" => 8,
+        "a" => 9,
+        "a#" => 10,
+        "b" => 11,
+        _ => return 0.0,
+    };
+    let Ok(octave) = octave.parse::<i32>() else { return 0.0 };
+    let midi = 12 * (octave + 1) + semitone;
+    440.0 * 2f32.powf((midi - 69) as f32 / 12.0)
+}
+
+fn drum_clock(name: &str) -> f32 {
+    match name {
+        "k" => MUSIC_KICK_CLOCK,
+        "s" => MUSIC_SNARE_CLOCK,
+        "h" => MUSIC_HAT_CLOCK,
+        _ => 0.0,
+    }
+}
+
+//16 bit mono pcm, the simplest wav there is
+pub fn to_wav(samples: &[f32]) -> Vec<u8> {
+    let data_length = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_length as usize);
+    bytes.extend(b"RIFF");
+    bytes.extend((36 + data_length).to_le_bytes());
+    bytes.extend(b"WAVEfmt ");
+    bytes.extend(16u32.to_le_bytes()); //format chunk length
+    bytes.extend(1u16.to_le_bytes()); //pcm
+    bytes.extend(1u16.to_le_bytes()); //one channel
+    bytes.extend(MUSIC_SAMPLE_RATE.to_le_bytes());
+    bytes.extend((MUSIC_SAMPLE_RATE * 2).to_le_bytes()); //bytes per second
+    bytes.extend(2u16.to_le_bytes()); //bytes per sample
+    bytes.extend(16u16.to_le_bytes()); //bits per sample
+    bytes.extend(b"data");
+    bytes.extend(data_length.to_le_bytes());
+    samples.iter().for_each(|sample| bytes.extend(((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes()));
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing() -> MusicControl {
+        let control = MusicControl::new();
+        control.set_playing(true);
+        control
+    }
+
+    #[test]
+    fn notes_parse_with_repeats_and_pitches() {
+        let notes = parse_notes("a4:2 [ c5:1 r:1 ]3", false);
+        assert_eq!(notes.len(), 7);
+        assert_eq!(notes[0].frequency, 440.0);
+        assert_eq!(notes.iter().map(|note| note.length).sum::<u32>(), 8);
+        assert!((pitch("c5") - 523.25).abs() < 0.01);
+        assert_eq!(pitch("r"), 0.0);
+    }
+
+    #[test]
+    fn render_is_the_same_every_time_and_silent_when_stopped() {
+        let tune = &TUNES[0];
+        let first = Synth::new(tune, playing()).render(4000);
+        assert_eq!(first, Synth::new(tune, playing()).render(4000));
+        assert!(first.iter().any(|sample| *sample != 0.0));
+        assert!(Synth::new(tune, MusicControl::new()).render(4000).iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn faster_tempo_makes_a_shorter_loop() {
+        let control = playing();
+        let synth = Synth::new(&TUNES[0], control.clone());
+        let normal = synth.loop_samples();
+        control.set_tempo(tempo(10, MUSIC_DANGER_HEIGHT));
+        assert!(synth.loop_samples() < normal);
+    }
+
+    #[test]
+    fn wav_has_a_pcm_header_and_clamped_samples() {
+        let wav = to_wav(&[0.0, 1.0, -2.0]);
+        let u32_at = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+        let i16_at = |at: usize| i16::from_le_bytes(wav[at..at + 2].try_into().unwrap());
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(4), 36 + 6);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(24), MUSIC_SAMPLE_RATE);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40), 6);
+        assert_eq!((i16_at(44), i16_at(46), i16_at(48)), (0, i16::MAX, -i16::MAX));
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rata_tetris::engine::{Game, GameMode, GameState};
+use crate::consts::*;
+
+//one line on a high score table
+#[derive(Clone)]
+pub struct HighScore {
+    pub name: String,
+    pub score: Score,
+    pub lines: u16,
+    pub level: u8,
+    pub date: u64, //seconds since the unix epoch
+    pub frames: u64,
+}
+
+impl HighScore {
+    //the game as it just ended, still waiting on a name
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            name: String::new(),
+            score: game.current_score,
+            lines: game.line_count,
+            level: game.current_level,
+            date: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default(),
+            frames: game.play_frames,
+        }
+    }
+
+    //sprints are ranked by time, everything else by score
+    fn beats(&self, other: &HighScore, mode: GameMode) -> bool {
+        match mode {
+            GameMode::Sprint => self.frames < other.frames,
+            GameMode::Marathon | GameMode::Ultra => self.score > other.score,
+        }
+    }
+}
+
+//a ranked table for each mode, best first
+#[derive(Default)]
+pub struct HighScores {
+    marathon: Vec<HighScore>,
+    sprint: Vec<HighScore>,
+    ultra: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn table(&self, mode: GameMode) -> &[HighScore] {
+        match mode {
+            GameMode::Marathon => &self.marathon,
+            GameMode::Sprint => &self.sprint,
+            GameMode::Ultra => &self.ultra,
+        }
+    }
+
+    fn table_mut(&mut self, mode: GameMode) -> &mut Vec<HighScore> {
+        match mode {
+            GameMode::Marathon => &mut self.marathon,
+            GameMode::Sprint => &mut self.sprint,
+            GameMode::Ultra => &mut self.ultra,
+        }
+    }
+
+    //where a finished game would land on its table - replays, empty scores and unfinished sprints don't make it on
+    pub fn rank(&self, game: &Game) -> Option<usize> {
+        if game.playback.is_some() { return None; }
+        let qualifies = match game.mode {
+            GameMode::Sprint => game.game_state == GameState::Finished,
+            GameMode::Marathon | GameMode::Ultra => game.current_score > 0,
+        };
+        if !qualifies { return None; }
+        let entry = HighScore::from_game(game);
+        let table = self.table(game.mode);
+        let rank = table.iter().position(|other| entry.beats(other, game.mode)).unwrap_or(table.len());
+        (rank < HIGH_SCORE_COUNT).then_some(rank)
+    }
+
+    //where the game that just ended sits on its table, once its name is in - none until then, or if it didn't make it
+    pub fn placing(&self, game: &Game) -> Option<usize> {
+        if game.playback.is_some() || !game.is_over() { return None; }
+        self.table(game.mode).iter().position(|entry| {
+            entry.score == game.current_score && entry.lines == game.line_count && entry.frames == game.play_frames
+        })
+    }
+
+    pub fn insert(&mut self, mode: GameMode, entry: HighScore) {
+        let table = self.table_mut(mode);
+        let rank = table.iter().position(|other| entry.beats(other, mode)).unwrap_or(table.len());
+        table.insert(rank, entry);
+        table.truncate(HIGH_SCORE_COUNT);
+    }
+
+    //one entry per line - mode, score, lines, level, date, frames, then the name last since it's the only free text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for mode in GameMode::ALL {
+            for entry in self.table(mode) {
+                //writing into a string can't fail
+                let _ = writeln!(text, "{} {} {} {} {} {} {}", mode.name(), entry.score, entry.lines, entry.level, entry.date, entry.frames, entry.name);
+            }
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut high_scores = Self::default();
+        for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let invalid = || format!("line {}: expected mode, score, lines, level, date, frames and name", index + 1);
+            let mut fields = line.splitn(7, ' ');
+            let mode = fields.next().and_then(GameMode::from_name).ok_or_else(invalid)?;
+            //each number has to fit its own type, anything out of range is as broken as anything else
+            let (Some(score), Some(lines), Some(level), Some(date), Some(frames), Some(name)) =
+                (number(fields.next()), number(fields.next()), number(fields.next()), number(fields.next()), number(fields.next()), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let entry = HighScore { name: name.to_string(), score, lines, level, date, frames };
+            high_scores.insert(mode, entry);
+        }
+        Ok(high_scores)
+    }
+}
+
+fn number<T: FromStr>(field: Option<&str>) -> Option<T> {
+    field?.parse().ok()
+}
+
+//a unix timestamp as a year-month-day date, in utc
+pub fn format_date(seconds: u64) -> String {
+    //days to a civil date, from howard hinnant's date algorithms
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: Score, frames: u64) -> HighScore {
+        HighScore { name: name.to_string(), score, lines: 10, level: 1, date: 0, frames }
+    }
+
+    fn names(high_scores: &HighScores, mode: GameMode) -> Vec<&str> {
+        high_scores.table(mode).iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn tables_keep_the_best_first_and_only_the_top_ten() {
+        let mut high_scores = HighScores::default();
+        (1..=12).for_each(|score| high_scores.insert(GameMode::Marathon, entry(&score.to_string(), score * 100, 0)));
+        let table = high_scores.table(GameMode::Marathon);
+        assert_eq!(table.len(), HIGH_SCORE_COUNT);
+        assert_eq!((table[0].score, table[HIGH_SCORE_COUNT - 1].score), (1200, 300));
+
+        //sprints go by the quickest time instead
+        high_scores.insert(GameMode::Sprint, entry("slow", 0, 5000));
+        high_scores.insert(GameMode::Sprint, entry("fast", 0, 3000));
+        assert_eq!(names(&high_scores, GameMode::Sprint), ["fast", "slow"]);
+    }
+
+    #[test]
+    fn rank_is_where_a_finished_game_would_land() {
+        let mut high_scores = HighScores::default();
+        let mut game = Game::new(1);
+        game.start_game();
+        assert_eq!(high_scores.rank(&game), None);
+        game.current_score = 550;
+        assert_eq!(high_scores.rank(&game), Some(0));
+        (1..=10).for_each(|score| high_scores.insert(GameMode::Marathon, entry("", score * 100, 0)));
+        assert_eq!(high_scores.rank(&game), Some(5));
+        game.current_score = 50;
+        assert_eq!(high_scores.rank(&game), None);
+
+        //a sprint only counts once it's run the full distance
+        game.mode = GameMode::Sprint;
+        assert_eq!(high_scores.rank(&game), None);
+        game.game_state = GameState::Finished;
+        assert_eq!(high_scores.rank(&game), Some(0));
+    }
+
+    #[test]
+    fn text_round_trips_and_refuses_numbers_out_of_range() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(GameMode::Ultra, entry("two words", 900, 7200));
+        high_scores.insert(GameMode::Sprint, entry("ace", 0, 3600));
+        let loaded = HighScores::from_text(&high_scores.to_text()).unwrap();
+        assert_eq!(loaded.to_text(), high_scores.to_text());
+        assert_eq!(names(&loaded, GameMode::Ultra), ["two words"]);
+
+        assert!(HighScores::from_text("marathon 100 10 1 0 600 ok").is_ok());
+        assert!(HighScores::from_text("marathon 100 10 256 0 600 too high a level").is_err());
+        assert!(HighScores::from_text("marathon 100 70000 1 0 600 too many lines").is_err());
+        assert!(HighScores::from_text("marathon 5000000000 10 1 0 600 too big a score").is_err());
+        assert!(HighScores::from_text("marathon 100 10 1 0 600").is_err());
+    }
+
+    #[test]
+    fn dates_are_utc_calendar_days() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_767_225_599), "2025-12-31");
+    }
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use dirs::{config_dir, data_dir, home_dir};
+
+use rata_tetris::engine::{GameMode, Replay, Snapshot};
+use crate::audio::{is_plain_name, SoundPack};
+use crate::config::{replace_keys, Config};
+use crate::keys::KeyBindings;
+use crate::consts::*;
+use crate::music::to_wav;
+use crate::scores::{HighScore, HighScores};
+use crate::theme::Theme;
+
+//everything the terminal front end keeps on disk - the engine itself never touches the filesystem
+
+//sprint personal bests are kept per user as the frame count at each split, the last being the finishing time
+pub fn load_sprint_best() -> Option<Vec<u64>> {
+    let text = fs::read_to_string(data_directory()?.join(SPRINT_BEST_FILENAME)).ok()?;
+    text.split_whitespace().map(|split| split.parse().ok()).collect()
+}
+
+pub fn save_sprint_best(splits: &[u64]) -> io::Result<()> {
+    let directory = data_directory().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for this user"))?;
+    fs::create_dir_all(&directory)?;
+    let text = splits.iter().map(|split| split.to_string()).collect::<Vec<String>>().join(" ");
+    fs::write(directory.join(SPRINT_BEST_FILENAME), text)
+}
+
+//the high score tables live in one file - a missing file is just an empty table, a broken one is an error
+pub fn load_high_scores() -> io::Result<HighScores> {
+    let Some(file_path) = data_directory().map(|directory| directory.join(HIGH_SCORES_FILENAME)) else { return Ok(HighScores::default()) };
+    if !file_path.exists() {
+        return Ok(load_legacy_top_score());
+    }
+    let text = fs::read_to_string(&file_path)?;
+    HighScores::from_text(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_high_scores(high_scores: &HighScores) -> io::Result<()> {
+    let directory = data_directory().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for this user"))?;
+    fs::create_dir_all(&directory)?;
+    fs::write(directory.join(HIGH_SCORES_FILENAME), high_scores.to_text())
+}
+
+//a game quit part way through waits here until the next launch carries on with it or starts over
+pub fn load_suspended_game() -> io::Result<Option<Snapshot>> {
+    let Some(file_path) = data_directory().map(|directory| directory.join(SUSPENDED_GAME_FILENAME)) else { return Ok(None) };
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&file_path)?;
+    Snapshot::from_text(&text).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_suspended_game(snapshot: &Snapshot) -> io::Result<()> {
+    let directory = data_directory().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for this user"))?;
+    fs::create_dir_all(&directory)?;
+    fs::write(directory.join(SUSPENDED_GAME_FILENAME), snapshot.to_text())
+}
+
+//once it's been picked back up or passed over the suspended game is gone
+pub fn remove_suspended_game() -> io::Result<()> {
+    let Some(file_path) = data_directory().map(|directory| directory.join(SUSPENDED_GAME_FILENAME)) else { return Ok(()) };
+    if !file_path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(file_path)
+}
+
+//older versions only kept a single top score in the home directory, carry it over onto the marathon table
+fn load_legacy_top_score() -> HighScores {
+    let mut high_scores = HighScores::default();
+    let score = home_dir()
+        .and_then(|home| fs::read_to_string(home.join(LEGACY_TOP_SCORE_FILENAME)).ok())
+        .and_then(|text| text.trim().parse::<Score>().ok())
+        .unwrap_or_default();
+    if score > 0 {
+        let entry = HighScore { name: LEGACY_HIGH_SCORE_NAME.to_string(), score, lines: 0, level: 0, date: 0, frames: 0 };
+        high_scores.insert(GameMode::Marathon, entry);
+    }
+    high_scores
+}
+
+//settings live in the user's config directory, and the defaults are written out there the first time the game runs
+pub fn load_config() -> io::Result<Config> {
+    let Some(directory) = config_dir().map(|directory| directory.join(CONFIG_DIRECTORY_NAME)) else { return Ok(Config::default()) };
+    let file_path = directory.join(CONFIG_FILENAME);
+    if !file_path.exists() {
+        if let Err(e) = fs::create_dir_all(&directory).and_then(|_| fs::write(&file_path, DEFAULT_CONFIG)) {
+            eprintln!("couldn't write default config file: {e}");
+        }
+        return Ok(Config::default());
+    }
+    let text = fs::read_to_string(&file_path)?;
+    Config::from_toml(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", file_path.display())))
+}
+
+//bindings from the controls screen go back into the config file, leaving the rest of it alone
+pub fn save_key_bindings(keys: &KeyBindings) -> io::Result<()> {
+    let directory = config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory for this user"))?
+        .join(CONFIG_DIRECTORY_NAME);
+    let file_path = directory.join(CONFIG_FILENAME);
+    let text = if file_path.exists() { fs::read_to_string(&file_path)? } else { DEFAULT_CONFIG.to_string() };
+    fs::create_dir_all(&directory)?;
+    fs::write(file_path, replace_keys(&text, keys))
+}
+
+//themes are settings like any other, so people's own live next to the config file - the built in ones come first
+pub fn load_theme(name: &str) -> io::Result<Theme> {
+    if let Some(theme) = Theme::built_in(name) {
+        return Ok(theme);
+    }
+    let directory = config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory for this user"))?
+        .join(CONFIG_DIRECTORY_NAME)
+        .join(THEME_DIRECTORY_NAME);
+    let file_path = directory.join(format!("{name}.{THEME_EXTENSION}"));
+    if !file_path.exists() {
+        let message = format!("\"{name}\" isn't one of {}, or a theme file in {}", Theme::built_in_names().join(", "), directory.display());
+        return Err(io::Error::new(io::ErrorKind::NotFound, message));
+    }
+    let text = fs::read_to_string(&file_path)?;
+    Theme::from_toml(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", file_path.display())))
+}
+
+fn data_directory() -> Option<PathBuf> {
+    data_dir().map(|directory| directory.join(DATA_DIRECTORY_NAME))
+}
+
+//replays land in the user's data directory, named after the time the game ended
+pub fn save_replay(replay: &Replay) -> io::Result<PathBuf> {
+    let directory = data_directory()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for this user"))?
+        .join(REPLAY_DIRECTORY_NAME);
+    fs::create_dir_all(&directory)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+    let file_path = directory.join(format!("{timestamp}.{REPLAY_EXTENSION}"));
+    fs::write(&file_path, replay.to_text())?;
+    Ok(file_path)
+}
+
+//sound packs are read in whole up front, so a broken manifest or a missing file shows up before the game starts
+pub fn load_sound_pack(name: &str) -> io::Result<SoundPack> {
+    if !is_plain_name(name) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("sound pack \"{name}\" has to be the name of a directory in {SOUND_PACK_DIRECTORY_NAME}")));
+    }
+    let directory = data_directory()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for this user"))?
+        .join(SOUND_PACK_DIRECTORY_NAME)
+        .join(name);
+    let manifest_path = directory.join(SOUND_PACK_MANIFEST);
+    let text = fs::read_to_string(&manifest_path).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", manifest_path.display())))?;
+    let files = SoundPack::files_from_toml(&text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", manifest_path.display())))?;
+    let sounds = files.into_iter().map(|(sound, file)| {
+        let file_path = directory.join(file);
+        fs::read(&file_path).map(|bytes| (sound, bytes)).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", file_path.display())))
+    }).collect::<io::Result<Vec<_>>>()?;
+    Ok(SoundPack::new(sounds))
+}
+
+//rendered music goes wherever it was asked to
+pub fn save_wav(file_path: &Path, samples: &[f32]) -> io::Result<()> {
+    fs::write(file_path, to_wav(samples))
+}
+
+pub fn load_replay(file_path: &Path) -> io::Result<Replay> {
+    let text = fs::read_to_string(file_path)?;
+    Replay::from_text(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
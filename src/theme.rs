@@ -0,0 +1,215 @@
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+use crate::config::location;
+use crate::consts::*;
+use crate::feedback::Flash;
+
+//a color as a theme file writes it - a 256 color index like 234, or a string that's "#rrggbb", a name like "white", or an index again
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Index(u8),
+    Text(String),
+}
+
+impl ColorValue {
+    fn color(&self, key: &str) -> Result<Color, String> {
+        match self {
+            Self::Index(index) => Ok(Color::Indexed(*index)),
+            Self::Text(text) => Color::from_str(text)
+                .map_err(|_| format!("{key}: \"{text}\" isn't a color - use a 256 color index, \"#rrggbb\", or a name like \"white\"")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    background: Option<ColorValue>,
+    text: Option<ColorValue>,
+    border: Option<ColorValue>,
+    dim: Option<ColorValue>,
+    highlight: Option<ColorValue>,
+    ghost: Option<ColorValue>,
+    flash_tetris: Option<ColorValue>,
+    flash_level_up: Option<ColorValue>,
+    flash_top_out: Option<ColorValue>,
+    flash_top_score: Option<ColorValue>,
+    pattern: Option<Vec<ColorValue>>,
+    levels: Option<Vec<LevelFile>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelFile {
+    name: Option<String>,
+    pieces: Vec<ColorValue>,
+    line_clear: ColorValue,
+}
+
+//the piece colors for a level, in mino id order, and the color full rows flash as they clear
+#[derive(Clone)]
+pub struct Palette {
+    name: Option<String>,
+    pub pieces: [Color; MINO_TYPES as usize],
+    pub line_clear: Color,
+}
+
+//every color the game draws with - the level palettes take turns, one per level and round again
+#[derive(Clone)]
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub border: Color,
+    pub dim: Color,
+    pub highlight: Color,
+    pub ghost: Option<Color>, //none draws the ghost in the falling piece's own color
+    pub flash_tetris: Color,
+    pub flash_level_up: Color,
+    pub flash_top_out: Color,
+    pub flash_top_score: Color,
+    pub pattern: [Color; THEME_PATTERN_LENGTH],
+    levels: Vec<Palette>,
+}
+
+impl Theme {
+    //a user's theme only needs what it changes, the rest comes from the levels theme
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        Self::parse(text, Some(&Self::default()))
+    }
+
+    //a built in theme by name, or one of the levels theme's palettes kept for the whole game
+    pub fn built_in(name: &str) -> Option<Self> {
+        if let Some((_, text)) = THEMES.iter().find(|(theme, _)| *theme == name) {
+            //the built in themes are all complete and known good
+            return Some(Self::parse(text, None).unwrap_or_else(|e| panic!("built in theme {name} is broken: {e}")));
+        }
+        let mut theme = Self::default();
+        let palette = theme.levels.iter().find(|palette| palette.name.as_deref() == Some(name))?.clone();
+        theme.levels = vec![palette];
+        Some(theme)
+    }
+
+    //everything built_in knows, for telling people what there is
+    pub fn built_in_names() -> Vec<String> {
+        let mut names = THEMES.iter().map(|(name, _)| name.to_string()).collect::<Vec<String>>();
+        names.extend(Self::default().levels.into_iter().filter_map(|palette| palette.name));
+        names
+    }
+
+    pub fn palette(&self, level: u8) -> &Palette {
+        &self.levels[level as usize % self.levels.len()]
+    }
+
+    //the color the border lights up in for an event
+    pub fn flash(&self, flash: Flash) -> Color {
+        match flash {
+            Flash::Tetris => self.flash_tetris,
+            Flash::LevelUp => self.flash_level_up,
+            Flash::TopOut => self.flash_top_out,
+            Flash::TopScore => self.flash_top_score,
+        }
+    }
+
+    fn parse(text: &str, base: Option<&Theme>) -> Result<Self, String> {
+        let file: ThemeFile = toml::from_str(text).map_err(|e| e.message().to_string() + &location(text, e.span()))?;
+
+        let color = |key: &str, value: &Option<ColorValue>, fallback: Option<Color>| match value {
+            Some(value) => value.color(key),
+            None => fallback.ok_or_else(|| format!("{key} is missing")),
+        };
+        let pattern = match &file.pattern {
+            Some(values) => colors("pattern", values)?,
+            None => base.map(|base| base.pattern).ok_or_else(|| "pattern is missing".to_string())?,
+        };
+        let levels = match &file.levels {
+            Some(levels) if levels.is_empty() => return Err("levels is empty, it needs at least one".to_string()),
+            Some(levels) => levels.iter().enumerate().map(|(index, level)| {
+                let key = format!("levels[{index}]");
+                Ok(Palette {
+                    name: level.name.clone(),
+                    pieces: colors(&format!("{key}.pieces"), &level.pieces)?,
+                    line_clear: level.line_clear.color(&format!("{key}.line_clear"))?,
+                })
+            }).collect::<Result<Vec<Palette>, String>>()?,
+            None => base.map(|base| base.levels.clone()).ok_or_else(|| "levels is missing".to_string())?,
+        };
+
+        Ok(Self {
+            background: color("background", &file.background, base.map(|base| base.background))?,
+            text: color("text", &file.text, base.map(|base| base.text))?,
+            border: color("border", &file.border, base.map(|base| base.border))?,
+            dim: color("dim", &file.dim, base.map(|base| base.dim))?,
+            highlight: color("highlight", &file.highlight, base.map(|base| base.highlight))?,
+            flash_tetris: color("flash_tetris", &file.flash_tetris, base.map(|base| base.flash_tetris))?,
+            flash_level_up: color("flash_level_up", &file.flash_level_up, base.map(|base| base.flash_level_up))?,
+            flash_top_out: color("flash_top_out", &file.flash_top_out, base.map(|base| base.flash_top_out))?,
+            flash_top_score: color("flash_top_score", &file.flash_top_score, base.map(|base| base.flash_top_score))?,
+            ghost: file.ghost.as_ref().map(|ghost| ghost.color("ghost")).transpose()?.or(base.and_then(|base| base.ghost)),
+            pattern,
+            levels,
+        })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let (name, text) = THEMES[0];
+        Self::parse(text, None).unwrap_or_else(|e| panic!("built in theme {name} is broken: {e}"))
+    }
+}
+
+//a fixed length list of colors, like a palette's pieces
+fn colors<const N: usize>(key: &str, values: &[ColorValue]) -> Result<[Color; N], String> {
+    if values.len() != N {
+        return Err(format!("{key} has {} colors, it needs {N}", values.len()));
+    }
+    let colors = values.iter().enumerate().map(|(index, value)| value.color(&format!("{key}[{index}]"))).collect::<Result<Vec<Color>, String>>()?;
+    Ok(colors.try_into().expect("length checked above"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_themes_fall_back_to_the_levels_theme() {
+        let base = Theme::default();
+        let theme = Theme::from_toml("text = \"red\"\nghost = \"#102030\"\nflash_tetris = 33\n").unwrap();
+        assert_eq!((theme.text, theme.ghost, theme.flash(Flash::Tetris)), (Color::Red, Some(Color::Rgb(16, 32, 48)), Color::Indexed(33)));
+        assert_eq!((theme.background, theme.pattern, theme.flash(Flash::TopOut)), (base.background, base.pattern, base.flash_top_out));
+        assert_eq!(theme.levels.len(), base.levels.len());
+        assert_eq!(theme.palette(3).pieces, base.palette(3).pieces);
+
+        //levels given replace the whole list, and go round again past the end
+        let theme = Theme::from_toml("[[levels]]\npieces = [1, 2, 3, 4, 5, 6, 7]\nline_clear = 8\n").unwrap();
+        assert_eq!(theme.palette(0).pieces, theme.palette(5).pieces);
+        assert_eq!(theme.palette(0).line_clear, Color::Indexed(8));
+    }
+
+    #[test]
+    fn built_in_themes_and_palettes() {
+        let names = Theme::built_in_names();
+        assert!(names.iter().all(|name| Theme::built_in(name).is_some()));
+        assert!(names.contains(&"gameboy".to_string()) && names.contains(&"lime".to_string()));
+        let lime = Theme::built_in("lime").unwrap();
+        assert_eq!(lime.palette(0).pieces, lime.palette(7).pieces);
+        assert!(Theme::built_in("plaid").is_none());
+        //without a base every color has to be there
+        assert_eq!(Theme::parse("text = \"red\"", None).err().unwrap_or_default(), "pattern is missing");
+    }
+
+    #[test]
+    fn broken_themes_say_what_is_wrong() {
+        let error = |text: &str| Theme::from_toml(text).err().unwrap_or_default();
+        assert!(error("text = \"blurple\"").starts_with("text: \"blurple\" isn't a color"));
+        assert_eq!(error("pattern = [1, 2]"), format!("pattern has 2 colors, it needs {THEME_PATTERN_LENGTH}"));
+        assert_eq!(error("levels = []"), "levels is empty, it needs at least one");
+        assert_eq!(error("[[levels]]\npieces = [1, 2, 3, 4, 5, 6, 7]\nline_clear = \"nope\""), "levels[0].line_clear: \"nope\" isn't a color - use a 256 color index, \"#rrggbb\", or a name like \"white\"");
+        assert!(error("\ntext = true").ends_with("(line 2)"));
+        assert!(error("sparkle = 3").starts_with("unknown field `sparkle`"));
+    }
+}
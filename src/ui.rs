@@ -1,24 +1,22 @@
+use crate::colors::{adapt_buffer, ColorDepth};
 use crate::consts::*;
+use crate::feedback::Flash;
+use crate::keys::{key_label, KeyBindings};
+use crate::scores::{format_date, HighScore, HighScores};
+use crate::theme::Theme;
 
-use once_cell::sync::Lazy;
-
-use crate::game::Game;
-
-use std::{
-    io, sync::{mpsc::Receiver, Arc, Mutex}, thread, time::Duration
-};
+use rata_tetris::engine::{Action, Game, GameMode, GameState, Ruleset, Snapshot};
+use rata_tetris::engine::scoring::LineClear;
 
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Offset, Rect},
-    style::{Color, Style, Stylize},
+    style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
     widgets::{Block, Paragraph, Widget},
-    DefaultTerminal
+    Frame
 };
 
-pub static BACKGROUND: Lazy<CachedBackground> = Lazy::new(|| CachedBackground::new());
-
 pub struct CachedBackground {
     pub widget: Paragraph<'static>,
     width: u16,
@@ -26,7 +24,7 @@ pub struct CachedBackground {
 }
 
 impl CachedBackground {
-    pub fn new() -> Self {
+    pub fn new(pattern: &[Color; THEME_PATTERN_LENGTH]) -> Self {
         let background_pattern = vec![
             vec![1, 1, 2, 1, 1, 1, 3, 3, 4, 4, 1, 1],
             vec![3, 3, 2, 2, 1, 2, 3, 3, 4, 1, 5, 1],
@@ -46,19 +44,7 @@ impl CachedBackground {
             .map(|row| {
                 Line::from(
                     row.iter()
-                        .map(|&cell| {
-                            let style = match cell {
-                                1 => Style::default().fg(Color::Indexed(237)),
-                                2 => Style::default().fg(Color::Indexed(238)),
-                                3 => Style::default().fg(Color::Indexed(236)),
-                                4 => Style::default().fg(Color::Indexed(235)),
-                                5 => Style::default().fg(Color::Indexed(237)),
-                                6 => Style::default().fg(Color::Indexed(235)),
-                                7 => Style::default().fg(Color::Indexed(239)),
-                                _ => Style::default().fg(Color::Indexed(235)),
-                            };
-                            Span::styled(BLOCK, style)
-                        })
+                        .map(|&cell| Span::styled(BLOCK, Style::default().fg(pattern[cell - 1])))
                         .collect::<Vec<Span>>(),
                 )
             })
@@ -104,13 +90,42 @@ impl Widget for &CachedBackground {
     }
 }
 
-impl Widget for &Game {
+//what the front end is showing on top of the game
+pub enum Screen {
+    Game,
+    HighScores(GameMode),
+    NameEntry { entry: HighScore, rank: usize },
+    Controls { selected: usize, slot: usize, capturing: bool, message: String },
+    Continue(Box<Snapshot>),
+}
+
+//how the game is drawn, from the config file - the background pattern is built once for the theme's colors
+pub struct DisplaySettings {
+    pub ghost: bool,
+    pub theme: Theme,
+    pub colors: ColorDepth,
+    background: CachedBackground,
+}
+
+impl DisplaySettings {
+    pub fn new(ghost: bool, theme: Theme, colors: ColorDepth) -> Self {
+        let background = CachedBackground::new(&theme.pattern);
+        Self { ghost, theme, colors, background }
+    }
+}
+
+//the game lives in the engine crate, so it's drawn through a thin wrapper - the last field is any border flash going on
+struct GameScreen<'a>(&'a Game, &'a HighScores, &'a DisplaySettings, &'a KeyBindings, Option<Flash>);
+
+impl Widget for GameScreen<'_> {
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized
     {
-        let bg_color = Color::Indexed(BACKGROUND_COLOR);
-        let alert_block = Block::bordered().style(Style::default().fg(Color::DarkGray).bg(bg_color));
+        let GameScreen(game, high_scores, settings, keys, flash) = self;
+        let theme = &settings.theme;
+        let bg_color = theme.background;
+        let alert_block = Block::bordered().style(Style::default().fg(theme.dim).bg(bg_color));
 
         //if the terminal is too small draw a message for now
         if area.width < SCREEN_WIDTH || area.height < SCREEN_HEIGHT {
@@ -121,30 +136,52 @@ impl Widget for &Game {
         let elements = build_element_rects(&area);
 
         //define some style rules
-        let bg_color = Color::Indexed(BACKGROUND_COLOR);
-        let block = Block::bordered();
-        let board_block = Block::bordered().style(Style::default().fg(Color::White).bg(bg_color));
+        let block = Block::bordered().border_style(Style::default().fg(theme.border));
+        let board_block = Block::bordered().style(Style::default().fg(theme.border).bg(bg_color));
         let block_no_border = Block::new();
+        //a flash lights up the border round the whole screen, and the board's own while there is one
+        let (screen_block, board_block) = match flash.map(|flash| theme.flash(flash)) {
+            Some(color) => (Block::bordered().border_style(Style::default().fg(color)), board_block.border_style(Style::default().fg(color))),
+            None => (Block::bordered(), board_block),
+        };
         let board_style = Style::default().fg(bg_color).bg(bg_color);
         let screen_style = Style::default().fg(bg_color).bg(bg_color);
-        let element_style = Style::default().fg(Color::White).bg(bg_color);
+        let element_style = Style::default().fg(theme.text).bg(bg_color);
         
         //draw the play area background, and the controls text at the bottom
-        draw_element(PRECALC_SCREEN, &elements[RECT_SCREEN], &block, &screen_style, buf);
-        draw_element(CONTROLS_TEXT, &elements[RECT_CONTROLS], &block_no_border, &element_style, buf);
+        draw_element(PRECALC_SCREEN, &elements[RECT_SCREEN], &screen_block, &screen_style, buf);
+        let controls_text = if game.playback.is_some() { REPLAY_CONTROLS_TEXT.to_string() } else { keys.controls_text() };
+        draw_element(&controls_text, &elements[RECT_CONTROLS], &block_no_border, &element_style, buf);
 
         //depending on the game state draw a different version of the screen
-        match self.game_state {
-            STATE_PLAYING => {
+        match game.game_state {
+            GameState::Playing => {
                 //draw board background, next piece background, and stats background
-                draw_element(TEXT_NEXT, &elements[RECT_NEXT], &block, &element_style, buf);
+                let mut next_rect = elements[RECT_NEXT];
+                next_rect.height -= (MAX_NEXT_COUNT - game.next_count) as u16 * NEXT_SLOT_HEIGHT;
+                let next_text = format!("{}{}", TEXT_NEXT, "\n ".repeat(next_rect.height as usize));
+                draw_element(&next_text, &next_rect, &block, &element_style, buf);
                 draw_element(TEXT_STATS, &elements[RECT_STATS], &block, &element_style, buf);
                 draw_element("", &elements[RECT_BOARD], &board_block, &board_style, buf);
 
                 //fill out line count, current and top scores, and the current level elements
-                draw_element(&format!("{}{:03}     ", TEXT_LINES, self.line_count), &elements[RECT_LINES], &block, &element_style, buf);
-                draw_element(&format!("\n {}\n     {:06} \n\n {}\n     {:06} \n ", "TOP", self.top_score, "SCORE", self.current_score), &elements[RECT_SCORES], &block, &element_style, buf);
-                draw_element(&format!("{}{:02}  ", TEXT_LEVEL, self.current_level), &elements[RECT_LEVEL], &block, &element_style, buf);                
+                draw_element(&format!("{}{:03}     ", TEXT_LINES, game.line_count), &elements[RECT_LINES], &block, &element_style, buf);
+                //sprint swaps the scores for the clock, and lists its splits against the personal best
+                //ultra counts the clock down over the score
+                match game.mode {
+                    GameMode::Marathon => draw_element(&format!("\n {}\n     {:06} \n\n {}\n     {:06} \n ", "TOP", game.top_score, "SCORE", game.current_score), &elements[RECT_SCORES], &block, &element_style, buf),
+                    GameMode::Sprint => {
+                        let best = game.best_splits.last().map_or(TEXT_NO_TIME.to_string(), |best| format_frames(*best));
+                        draw_element(&format!("\n {}\n {:>10} \n\n {}\n {:>10} \n ", "BEST", best, "TIME", format_frames(game.play_frames)), &elements[RECT_SCORES], &block, &element_style, buf);
+                        draw_element(&splits_text(game), &elements[RECT_SPLITS], &block, &element_style, buf);
+                    },
+                    GameMode::Ultra => draw_element(&format!("\n {}\n {:>10} \n\n {}\n     {:06} \n ", "TIME", format_frames(game.time_left()), "SCORE", game.current_score), &elements[RECT_SCORES], &block, &element_style, buf),
+                }
+                //outside of sprint the splits panel calls out the last special clear instead
+                if let Some(clear) = game.last_clear.filter(|_| game.mode != GameMode::Sprint) {
+                    draw_element(&clear_text(&clear), &elements[RECT_SPLITS], &block, &element_style, buf);
+                }
+                draw_element(&format!("{}{:02}  ", TEXT_LEVEL, game.current_level), &elements[RECT_LEVEL], &block, &element_style, buf);                
 
                 //iteate through and draw each stat item, and it's corresponding counted value
                 let stats_boxes = Layout::new(
@@ -153,15 +190,15 @@ impl Widget for &Game {
                 )
                 .split(elements[RECT_STATS_INSET]);
                 stats_boxes.iter().enumerate().for_each(|(index, rect)| {
-                    let mino_style = mino_to_styling(index as u8 + 1, self.current_level);
+                    let mino_style = mino_to_styling(index as u8 + 1, settings, game.current_level);
                     let number_display_box = Layout::new(Direction::Horizontal, Constraint::from_percentages([60, 40]))
                         .split(*rect)[1];
                     draw_element(mino_style.0.as_str(), rect, &block_no_border, &mino_style.1, buf);
-                    draw_element(&format!(" {:03}", self.statistics[index]), &number_display_box, &block_no_border, &element_style, buf);
+                    draw_element(&format!(" {:03}", game.statistics[index]), &number_display_box, &block_no_border, &element_style, buf);
                 });
 
                 //iterate through and draw the cells on the board
-                self.board_state.iter().enumerate().for_each(|(cell_y, row)| {
+                game.board_state.iter().enumerate().for_each(|(cell_y, row)| {
                     row.iter().enumerate().for_each(|(cell_x, value)| {
                         let board_rect = &elements[RECT_BOARD];
 
@@ -173,34 +210,46 @@ impl Widget for &Game {
                         );
 
                         //styling for all other blocks
-                        let mut style = mino_to_styling(*value, self.current_level);
-                        style.0 = BLOCK.to_string();
+                        let mut style = mino_to_styling(*value, settings, game.current_level);
+                        style.0 = mino_block(*value, settings.colors).to_string();
 
                         //styling rules for cleared rows
-                        if self.rows_cleared.contains(&cell_y) { 
+                        if game.rows_cleared.contains(&cell_y) { 
                             style.0 = CLEAR.to_string();
-                            style.1 = line_clear_style(self.current_level);
+                            style.1 = line_clear_style(settings, game.current_level);
                         };
 
                         Paragraph::new(style.0).style(style.1).render(cell_rect, buf);
                     });
                 });
                 
-                //draw the next piece to the next inset - change which appears in the box if rows are clearing
-                let next_mino_id = if self.rows_cleared.is_empty() { self.next_mino.selected_mino } else { self.current_mino.selected_mino };
-                let next_mino_style = mino_to_styling(next_mino_id, self.current_level);
-                draw_element(next_mino_style.0.as_str(), &elements[RECT_NEXT_INSET], &block_no_border, &next_mino_style.1, buf);                
+                //draw the preview queue down the next panel
+                game.next_queue.iter().take(game.next_count).enumerate().for_each(|(slot, next_mino)| {
+                    let next_mino_style = mino_to_styling(next_mino.selected_mino, settings, game.current_level);
+                    let slot_rect = elements[RECT_NEXT_INSET].offset(Offset { x: 0, y: (slot as u16 * NEXT_SLOT_HEIGHT) as i32 });
+                    draw_element(next_mino_style.0.as_str(), &slot_rect, &block_no_border, &next_mino_style.1, buf);
+                });
+
+                //draw the held mino, greyed out while hold can't be used again until the current mino locks - no hold panel on the nes
+                if game.ruleset != Ruleset::Nes {
+                    draw_element(TEXT_HOLD, &elements[RECT_HOLD], &block, &element_style, buf);
+                    if let Some(hold_mino) = &game.hold_mino {
+                        let mut hold_mino_style = mino_to_styling(hold_mino.selected_mino, settings, game.current_level);
+                        if game.hold_used { hold_mino_style.1 = hold_mino_style.1.fg(theme.dim); }
+                        draw_element(hold_mino_style.0.as_str(), &elements[RECT_HOLD_INSET], &block_no_border, &hold_mino_style.1, buf);
+                    }
+                }
 
                 //draw the current falling mino onto the screen - skip doing this at all if paused for row clears
-                if !self.rows_cleared.is_empty() { return };
-                self.current_mino.get_rotation().iter().enumerate().for_each(|(y, row)| {
+                if !game.rows_cleared.is_empty() { return };
+                game.current_mino.get_rotation().iter().enumerate().for_each(|(y, row)| {
                     row.iter().enumerate().for_each(|(x, value)| {
                         if *value != 0 {
                             let board_rect = &elements[RECT_BOARD];
 
                             let cell_screen_position: BoardXY = (
-                                board_rect.x as i16 + self.current_mino_position.0,
-                                board_rect.y as i16 + self.current_mino_position.1,
+                                board_rect.x as i16 + game.current_mino_position.0,
+                                board_rect.y as i16 + game.current_mino_position.1,
                             );
 
                             let cell_rect = Rect::new(
@@ -211,34 +260,77 @@ impl Widget for &Game {
                             );
 
                             
-                            let slamming_by = self.slam_offset.1 as u16;
+                            let slamming_by = game.slam_offset.1 as u16;
                             let ghost_rect = cell_rect.offset(Offset { x: 0, y: slamming_by as i32 });
-                            let style = mino_to_styling(self.current_mino.selected_mino, self.current_level);
-                            draw_element(BLOCK, &ghost_rect, &block, &style.1, buf); //this accidentally made a really nice colored ghost piece out of borders amusingly
+                            let style = mino_to_styling(game.current_mino.selected_mino, settings, game.current_level);
+                            if settings.ghost && game.ruleset != Ruleset::Nes { //no ghost on the nes
+                                let ghost_style = theme.ghost.map_or(style.1, |color| style.1.fg(color));
+                                draw_element(BLOCK, &ghost_rect, &Block::bordered(), &ghost_style, buf); //this accidentally made a really nice colored ghost piece out of borders amusingly
+                            }
                             if cell_rect.y <= board_rect.y { return; } //don't draw pieces if off screen
-                            draw_element(BLOCK, &cell_rect, &block_no_border, &style.1, buf);
+                            draw_element(mino_block(game.current_mino.selected_mino, settings.colors), &cell_rect, &block_no_border, &style.1, buf);
                             
                         }
                     });
                 });
             }
             //draw corresponding game screens for the other states
-            STATE_PAUSED => draw_element(BIG_TEXT_PAUSED, &elements[RECT_BIG_TEXT], &block, &element_style, buf),
-            STATE_START_SCREEN => {
+            GameState::Paused => draw_element(BIG_TEXT_PAUSED, &elements[RECT_BIG_TEXT], &block, &element_style, buf),
+            GameState::StartScreen => {
                 draw_element(BIG_TEXT_TETRIS, &elements[RECT_BIG_TEXT], &block, &element_style, buf);
-                draw_element(ZOOM_TIP_TEXT, &elements[RECT_ZOOM_TIP], &block, &Style::new().bg(bg_color).fg(Color::DarkGray), buf);
+                draw_element(ZOOM_TIP_TEXT, &elements[RECT_ZOOM_TIP], &Block::bordered(), &Style::new().bg(bg_color).fg(theme.dim), buf);
+                let mode_message = format!("MODE: {}   RULES: {}   LEVEL: {}", game.mode.name().to_uppercase(), game.ruleset.name().to_uppercase(), game.start_level);
+                let keys_message = "m: mode   r: rules   ←→: level   h: high scores   k: controls";
+                draw_element(&format!("{mode_message:^SCORE_PADDING$}\n{keys_message:^SCORE_PADDING$}"), &elements[RECT_MODE], &block, &element_style, buf);
             },
-            STATE_GAME_OVER => {
+            GameState::Finished if game.mode == GameMode::Ultra => {
+                let result_uncentered = match high_scores.placing(game) {
+                    Some(0) => format!("⏱  Time up! {} points tops your ultra scores! ⏱ ", game.current_score),
+                    Some(rank) => format!("Time up! {} points, number {} on your ultra scores.", game.current_score, rank + 1),
+                    None => format!("Time up! You scored {} points.", game.current_score),
+                };
+                let result_message = format!("{result_uncentered:^SCORE_PADDING$}");
+
+                let scores_uncentered = match high_scores.table(GameMode::Ultra).first() {
+                    Some(best) => format!("Best: {} by {}", best.score, best.name),
+                    None => String::new(),
+                };
+                let scores_message = format!("{scores_uncentered:^SCORE_PADDING$}");
+
+                draw_element(TIME_UP_TEXT, &elements[RECT_GAME_OVER_TEXT], &block, &element_style, buf);
+                draw_element(result_message.as_str(), &elements[RECT_NEW_TOP_SCORE], &block, &element_style, buf);
+                draw_element(scores_message.as_str(), &elements[RECT_GAME_OVER_STATS], &block, &element_style, buf);
+            },
+            GameState::Finished => {
+                let time = format_frames(game.play_frames);
+                let result_uncentered = match game.best_splits.last() {
+                    _ if game.is_new_personal_best() => format!("🏁 New personal best of {time}! 🏁"),
+                    Some(best) if game.playback.is_none() => format!("You finished in {time}, {} on your best of {}.", format_frame_diff(game.play_frames, *best), format_frames(*best)),
+                    _ => format!("Finished {SPRINT_LINES} lines in {time}."),
+                };
+                let result_message = format!("{result_uncentered:^SCORE_PADDING$}");
+
+                let splits_uncentered = format!("Splits: {}", game.splits.iter().map(|split| format_frames(*split)).collect::<Vec<String>>().join("  "));
+                let splits_message = format!("{splits_uncentered:^SCORE_PADDING$}");
+
+                draw_element(BIG_TEXT_FINISH, &elements[RECT_BIG_TEXT], &block, &element_style, buf);
+                draw_element(result_message.as_str(), &elements[RECT_NEW_TOP_SCORE], &block, &element_style, buf);
+                draw_element(splits_message.as_str(), &elements[RECT_GAME_OVER_STATS], &block, &element_style, buf);
+            },
+            GameState::GameOver => {
                 
-                let score_message = if self.current_score > self.top_score {
-                    let message = format!("🎆 New top score set at {}! 🎇", self.current_score);
+                let score_message = if game.is_new_top_score() {
+                    let message = format!("🎆 New top score set at {}! 🎇", game.current_score);
                     format!("{message:^SCORE_PADDING$}") // ?
+                } else if let Some(rank) = high_scores.placing(game) {
+                    let message = format!("You scored {} points, number {} on your {} scores.", game.current_score, rank + 1, game.mode.name());
+                    format!("{message:^SCORE_PADDING$}")
                 } else {
-                    let message = format!("You scored {} points.", self.current_score);
+                    let message = format!("You scored {} points.", game.current_score);
                     format!("{message:^SCORE_PADDING$}")
                 };
 
-                let stats_uncentered = format!("Reach level {}, and cleared {} lines.", self.current_level, self.line_count);
+                let stats_uncentered = format!("Reach level {}, and cleared {} lines.", game.current_level, game.line_count);
                 let stats_message = format!("{stats_uncentered:^SCORE_PADDING$}");
 
                 draw_element(GAME_OVER_TEXT, &elements[RECT_GAME_OVER_TEXT], &block, &element_style, buf);
@@ -246,37 +338,173 @@ impl Widget for &Game {
                 draw_element(stats_message.as_str(), &elements[RECT_GAME_OVER_STATS], &block, &element_style, buf);
 
             }
-            _ => {}
         }
     }
 }
 
-pub fn draw_ui(
-	mut terminal: DefaultTerminal, 
-	game_state: Arc<Mutex<Game>>, 
-	stop_receiver: Receiver<()>
-) -> io::Result<()> {
-    loop {
-        thread::sleep(Duration::from_millis(16));
-        match stop_receiver.try_recv() {
-            Err(std::sync::mpsc::TryRecvError::Empty) => {}, 
-            _ => break,
+//a mode's table, reached from the start screen and shown after a name is entered
+struct HighScoresScreen<'a>(&'a HighScores, GameMode, &'a Theme);
+
+impl Widget for HighScoresScreen<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let HighScoresScreen(high_scores, mode, theme) = self;
+        if area.width < SCREEN_WIDTH || area.height < SCREEN_HEIGHT { return; }
+        let elements = build_element_rects(&area);
+
+        let bg_color = theme.background;
+        let block = Block::bordered().border_style(Style::default().fg(theme.border));
+        let screen_style = Style::default().fg(bg_color).bg(bg_color);
+        let element_style = Style::default().fg(theme.text).bg(bg_color);
+
+        draw_element(PRECALC_SCREEN, &elements[RECT_SCREEN], &Block::bordered(), &screen_style, buf);
+        draw_element(HIGH_SCORES_CONTROLS_TEXT, &elements[RECT_CONTROLS], &Block::new(), &element_style, buf);
+
+        let title = format!("HIGH SCORES - {}", mode.name().to_uppercase());
+        let mut rows = vec![format!("{title:^SCORE_PADDING$}"), String::new(), high_score_row("#", "NAME", "SCORE", "LINES", "LEVEL", "TIME", "DATE")];
+        let table = high_scores.table(mode);
+        rows.extend(table.iter().enumerate().map(|(rank, entry)| {
+            let date = if entry.date == 0 { "-".to_string() } else { format_date(entry.date) };
+            let time = if entry.frames == 0 { TEXT_NO_TIME.to_string() } else { format_frames(entry.frames) };
+            high_score_row(&(rank + 1).to_string(), &entry.name, &entry.score.to_string(), &entry.lines.to_string(), &entry.level.to_string(), &time, &date)
+        }));
+        if table.is_empty() {
+            rows.push(format!("{:^SCORE_PADDING$}", "no scores yet"));
         }
+        draw_element(&rows.join("\n"), &elements[RECT_MENU], &block, &element_style, buf);
+    }
+}
 
-        terminal.draw(|frame| {
+//every action and the keys on it - enter waits for a new key for the picked action
+struct ControlsScreen<'a>(&'a KeyBindings, usize, usize, bool, &'a str, &'a Theme);
 
-            frame.render_widget(&*BACKGROUND, frame.area());
+impl Widget for ControlsScreen<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let ControlsScreen(keys, selected, slot, capturing, message, theme) = self;
+        if area.width < SCREEN_WIDTH || area.height < SCREEN_HEIGHT { return; }
+        let elements = build_element_rects(&area);
+
+        let bg_color = theme.background;
+        let block = Block::bordered().border_style(Style::default().fg(theme.border));
+        let screen_style = Style::default().fg(bg_color).bg(bg_color);
+        let element_style = Style::default().fg(theme.text).bg(bg_color);
+
+        draw_element(PRECALC_SCREEN, &elements[RECT_SCREEN], &Block::bordered(), &screen_style, buf);
+        draw_element(KEYS_CONTROLS_TEXT, &elements[RECT_CONTROLS], &Block::new(), &element_style, buf);
+
+        let mut rows = vec![format!("{:^SCORE_PADDING$}", "CONTROLS"), String::new()];
+        rows.extend(Action::ALL.iter().enumerate().map(|(index, action)| {
+            let marker = if index == selected { ">" } else { " " };
+            let mut labels = keys.keys(*action).iter().map(|code| key_label(*code)).collect::<Vec<String>>();
+            //the picked key is bracketed, with a spare slot on the end of the picked action for adding one
+            if index == selected {
+                labels.push("+".to_string());
+                let picked = slot.min(labels.len() - 1);
+                labels[picked] = format!("[{}]", labels[picked]);
+            }
+            let bound = if index == selected && capturing { "press a key... (esc to cancel)".to_string() } else { labels.join(", ") };
+            format!("   {marker} {:<14} {bound}", action.name().replace('_', " "))
+        }));
+        rows.push(String::new());
+        rows.push(format!("{message:^SCORE_PADDING$}"));
+        draw_element(&rows.join("\n"), &elements[RECT_MENU], &block, &element_style, buf);
+    }
+}
 
-            let mut game = game_state.lock().unwrap();
+//asks for a name once a finished game makes it onto its table, over the game's stats line
+struct NameEntryPrompt<'a>(&'a HighScore, usize, GameMode, &'a Theme);
 
-            game.update();
+impl Widget for NameEntryPrompt<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let NameEntryPrompt(entry, rank, mode, theme) = self;
+        if area.width < SCREEN_WIDTH || area.height < SCREEN_HEIGHT { return; }
+        let elements = build_element_rects(&area);
+        let element_style = Style::default().fg(theme.highlight).bg(theme.background);
+        let prompt = format!("Number {} on the {} table! Name: {}_  (enter to save)", rank + 1, mode.name(), entry.name);
+        draw_element(&format!("{prompt:^SCORE_PADDING$}"), &elements[RECT_GAME_OVER_STATS], &Block::bordered(), &element_style, buf);
+    }
+}
 
-            frame.render_widget(&*game, frame.area());
+//offers the game that was quit part way through last time, over the start screen's mode picker
+struct ContinuePrompt<'a>(&'a Snapshot, &'a Theme);
 
-        })
-        .map(|_| ())?;
+impl Widget for ContinuePrompt<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let ContinuePrompt(snapshot, theme) = self;
+        if area.width < SCREEN_WIDTH || area.height < SCREEN_HEIGHT { return; }
+        let elements = build_element_rects(&area);
+        let element_style = Style::default().fg(theme.highlight).bg(theme.background);
+        let game_message = format!(
+            "CONTINUE? {} {}   SCORE: {}   LEVEL: {}   LINES: {}",
+            snapshot.mode.name().to_uppercase(),
+            format_frames(snapshot.play_frames),
+            snapshot.score,
+            snapshot.level,
+            snapshot.lines,
+        );
+        let keys_message = "enter: continue   esc: new game";
+        draw_element(&format!("{game_message:^SCORE_PADDING$}\n{keys_message:^SCORE_PADDING$}"), &elements[RECT_MODE], &Block::bordered(), &element_style, buf);
     }
-    Ok(())
+}
+
+fn high_score_row(rank: &str, name: &str, score: &str, lines: &str, level: &str, time: &str, date: &str) -> String {
+    format!("  {rank:>2}  {name:<8}  {score:>7}  {lines:>5}  {level:>5}  {time:>9}  {date:>10}")
+}
+
+pub fn draw_ui(frame: &mut Frame, game: &Game, screen: &Screen, high_scores: &HighScores, keys: &KeyBindings, settings: &DisplaySettings, flash: Option<Flash>) {
+    let theme = &settings.theme;
+    //the pattern is all solid blocks, there's nothing to see of it without color
+    if settings.colors != ColorDepth::Mono {
+        frame.render_widget(&settings.background, frame.area());
+    }
+    match screen {
+        Screen::Game => frame.render_widget(GameScreen(game, high_scores, settings, keys, flash), frame.area()),
+        Screen::HighScores(mode) => frame.render_widget(HighScoresScreen(high_scores, *mode, theme), frame.area()),
+        Screen::Controls { selected, slot, capturing, message } => frame.render_widget(ControlsScreen(keys, *selected, *slot, *capturing, message, theme), frame.area()),
+        Screen::NameEntry { entry, rank } => {
+            frame.render_widget(GameScreen(game, high_scores, settings, keys, flash), frame.area());
+            frame.render_widget(NameEntryPrompt(entry, *rank, game.mode, theme), frame.area());
+        },
+        Screen::Continue(snapshot) => {
+            frame.render_widget(GameScreen(game, high_scores, settings, keys, flash), frame.area());
+            frame.render_widget(ContinuePrompt(snapshot, theme), frame.area());
+        },
+    }
+    //everything's drawn in the theme's own colors, then brought down to whatever the terminal can show
+    adapt_buffer(frame.buffer_mut(), settings.colors);
+}
+
+//frames on the clock as minutes, seconds and hundredths
+fn format_frames(frames: u64) -> String {
+    let hundredths = (frames as f64 / FRAME_RATE_NTSC * 100.0).round() as u64;
+    format!("{}:{:02}.{:02}", hundredths / 6000, hundredths / 100 % 60, hundredths % 100)
+}
+
+//how far ahead (-) or behind (+) a time is against the best
+fn format_frame_diff(frames: u64, best: u64) -> String {
+    format!("{:+.2}", (frames as f64 - best as f64) / FRAME_RATE_NTSC)
+}
+
+//one row per split taken so far, the ones still to come are left blank
+fn splits_text(game: &Game) -> String {
+    (1..SPRINT_LINES / SPRINT_SPLIT_LINES).map(|split| {
+        let lines = split * SPRINT_SPLIT_LINES;
+        match game.splits.get(split as usize - 1) {
+            Some(frames) => {
+                let diff = game.best_splits.get(split as usize - 1).map_or(String::new(), |best| format_frame_diff(*frames, *best));
+                format!(" {lines:>2}  {:>8}  {diff:>7}", format_frames(*frames))
+            },
+            None => format!(" {lines:>2}  {TEXT_NO_TIME:>8}"),
+        }
+    }).collect::<Vec<String>>().join("\n")
+}
+
+//the clear's name, then any chains it kept going, then a perfect clear
+fn clear_text(clear: &LineClear) -> String {
+    let mut chains = vec![];
+    if clear.back_to_back { chains.push("BACK-TO-BACK".to_string()); }
+    if clear.combo > 0 { chains.push(format!("{} REN", clear.combo)); }
+    let perfect = if clear.perfect { "PERFECT CLEAR" } else { "" };
+    format!(" {}\n {}\n {}", clear.name(), chains.join("  "), perfect)
 }
 
 fn build_element_rects(area: &Rect) -> Vec<Rect> {
@@ -312,6 +540,11 @@ fn build_element_rects(area: &Rect) -> Vec<Rect> {
     rects.push(create_rect(GAME_OVER_STATS_XY, GAME_OVER_STATS_WIDTH, GAME_OVER_STATS_HEIGHT));
     rects.push(create_rect(NEW_TOP_SCORE_XY, NEW_TOP_SCORE_WIDTH, NEW_TOP_SCORE_HEIGHT));
     rects.push(create_rect(ZOOM_TIP_XY, ZOOM_TIP_WIDTH, ZOOM_TIP_HEIGHT));
+    rects.push(create_rect(HOLD_XY, HOLD_WIDTH, HOLD_HEIGHT));
+    rects.push(create_rect(HOLD_INSET_XY, HOLD_INSET_WIDTH, HOLD_INSET_HEIGHT));
+    rects.push(create_rect(SPLITS_XY, SPLITS_WIDTH, SPLITS_HEIGHT));
+    rects.push(create_rect(MODE_XY, MODE_WIDTH, MODE_HEIGHT));
+    rects.push(create_rect(MENU_XY, MENU_WIDTH, MENU_HEIGHT));
 
     rects
 }
@@ -331,7 +564,7 @@ fn draw_element(text: &str, rect: &Rect, block: &Block, style: &Style, buf: &mut
         .render(*rect, buf);
 }
 
-fn mino_to_styling(id: u8, current_level: u8) -> (String, Style) {
+fn mino_to_styling(id: u8, settings: &DisplaySettings, level: u8) -> (String, Style) {
 
     let mino_text = match id {
         I_BLOCK => TEXT_MINO_I,
@@ -344,33 +577,24 @@ fn mino_to_styling(id: u8, current_level: u8) -> (String, Style) {
         _ => "",
     };
 
-    let palette = get_level_pallete(current_level);
-
-    let bg_color = Color::Indexed(BACKGROUND_COLOR);
-    let fg_color = if id == 0 { bg_color } else { Color::Indexed(palette[id as usize - 1]) };
+    let theme = &settings.theme;
+    let bg_color = theme.background;
+    let fg_color = if id == 0 { bg_color } else { theme.palette(level).pieces[id as usize - 1] };
 
-    (mino_text.to_string(), Style::default().fg(fg_color).bg(bg_color))
+    (mino_text.replace(BLOCK, mino_block(id, settings.colors)), Style::default().fg(fg_color).bg(bg_color))
 }
 
-fn line_clear_style(current_level: u8) -> Style {
-    let palette = get_level_pallete(current_level);
-    let color_index = palette[LEVEL_PALLETE_LENGH - 1];
-    let bg_color = Color::Indexed(BACKGROUND_COLOR);
-    let fg_color = Color::Indexed(color_index);
-    Style::new().fg(fg_color).bg(bg_color)
+//one cell of a mino - in mono the texture is all that tells the types apart
+fn mino_block(id: u8, colors: ColorDepth) -> &'static str {
+    match colors {
+        ColorDepth::Mono if id != 0 => MONO_TEXTURES[id as usize - 1],
+        _ => BLOCK,
+    }
 }
 
-fn get_level_pallete(current_level: u8) -> [u8; LEVEL_PALLETE_LENGH]{
-    [
-        PALETTE_BLURPLE,
-        PALETTE_LIME,
-        PALETTE_PINK,
-        PALETTE_SWAMP,
-        PALETTE_MELON,
-        PALETTE_LAKE,
-        PALETTE_FACTORY,
-        PALETTE_MUAVE,
-        PALETTE_NARU,
-        PALETTE_CREAM
-    ][(current_level % 10) as usize]
+//in mono clearing rows are shown reversed, since they can't change color
+fn line_clear_style(settings: &DisplaySettings, level: u8) -> Style {
+    let theme = &settings.theme;
+    let style = Style::new().fg(theme.palette(level).line_clear).bg(theme.background);
+    if settings.colors == ColorDepth::Mono { style.add_modifier(Modifier::REVERSED) } else { style }
 }
\ No newline at end of file